@@ -0,0 +1,157 @@
+import test from 'ava';
+import { createRequire } from 'module';
+
+/**
+ * These tests run the copy process against the in-memory `FakeDesktop`, so no real keyboard input
+ * is simulated and the real clipboard is not touched. It is only available in the binary built
+ * with `yarn build:fake`, and the tests are skipped otherwise.
+ */
+const require = createRequire(import.meta.url);
+let FakeDesktop;
+try {
+	({ FakeDesktop } = require('../fake.js'));
+} catch {}
+const fakeTest = FakeDesktop ? test : test.skip;
+
+fakeTest('getSelectionText copies the selection and restores the clipboard', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous' });
+	t.is(desktop.getSelectionText(), 'Selected');
+	t.is(desktop.clipboardText, 'Previous');
+	t.is(desktop.copyCount, 1);
+});
+
+fakeTest('getSelectionText keeps the copied selection if restoreClipboard is false', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous' });
+	t.is(desktop.getSelectionText({ restoreClipboard: false }), 'Selected');
+	t.is(desktop.clipboardText, 'Selected');
+});
+
+fakeTest('getSelectionText returns an empty string without selection', (t) => {
+	const desktop = new FakeDesktop({ clipboardText: 'Previous' });
+	t.is(desktop.getSelectionText(), '');
+	t.is(desktop.clipboardText, 'Previous');
+});
+
+fakeTest('getSelectionText waits for a slow copy until the timeout', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', copyDelayMs: 30 });
+	t.is(desktop.getSelectionText({ timeoutMs: 500 }), 'Selected');
+	t.is(desktop.getSelectionText({ timeoutMs: 10 }), '');
+	t.is(desktop.clipboardText, null);
+});
+
+fakeTest('getSelection returns the HTML of the selection', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Bold', selectionHtml: '<b>Bold</b>' });
+	const selection = desktop.getSelection();
+	t.is(selection.text, 'Bold');
+	t.is(selection.html, '<b>Bold</b>');
+	t.deepEqual(selection.offeredFormats, ['text/plain', 'text/html']);
+	t.is(selection.strategy, 'copy');
+});
+
+fakeTest('getSelectionText throws KEY_SIMULATION_FAILED and restores the clipboard if the copy fails', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous', failCopy: true });
+	const error = t.throws(() => desktop.getSelectionText());
+	t.is(error.code, 'KEY_SIMULATION_FAILED');
+	t.is(desktop.clipboardText, 'Previous');
+});
+
+fakeTest('getSelectionText tries the copy shortcuts in order', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', appCopyShortcut: 'Ctrl+Shift+C' });
+	t.is(desktop.getSelectionText({ copyShortcut: ['Ctrl+Insert', 'Ctrl+Shift+C'], timeoutMs: 20 }), 'Selected');
+	t.is(desktop.copyCount, 1);
+});
+
+fakeTest('getSelectionText throws INVALID_SHORTCUT for an unknown key', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected' });
+	const error = t.throws(() => desktop.getSelectionText({ copyShortcut: 'Ctrl+Foo' }));
+	t.is(error.code, 'INVALID_SHORTCUT');
+	t.is(desktop.copyCount, 0);
+});
+
+fakeTest('getSelectionText throws FOCUS_CHANGED if the focus moves during the copy', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous', focusSwitches: 1 });
+	const error = t.throws(() => desktop.getSelectionText());
+	t.is(error.code, 'FOCUS_CHANGED');
+	t.is(desktop.clipboardText, 'Previous');
+});
+
+fakeTest('getSelectionText retries if the focus moves during the copy', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', focusSwitches: 1 });
+	t.is(desktop.getSelectionText({ focusChangePolicy: 'retry' }), 'Selected');
+	t.is(desktop.copyCount, 2);
+});
+
+fakeTest('replaceSelectionText pastes the text and restores the clipboard', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous' });
+	desktop.replaceSelectionText('Replaced', { pasteWaitTimeMs: 10 });
+	t.is(desktop.selectionText, 'Replaced');
+	t.is(desktop.clipboardText, 'Previous');
+	t.is(desktop.pasteCount, 1);
+});
+
+fakeTest('replaceSelectionText simulates the pasteShortcut', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', appPasteShortcut: 'Shift+Insert' });
+	desktop.replaceSelectionText('Replaced', { pasteWaitTimeMs: 10, pasteShortcut: 'Shift+Insert' });
+	t.is(desktop.selectionText, 'Replaced');
+	t.is(desktop.clipboardText, null);
+});
+
+fakeTest('typeText types the text without touching the clipboard', (t) => {
+	const desktop = new FakeDesktop({ clipboardText: 'Previous' });
+	desktop.typeText('Grüße', { chunkSize: 2 });
+	t.is(desktop.typedText, 'Grüße');
+	t.is(desktop.clipboardText, 'Previous');
+});
+
+fakeTest('getSelectionText excludes the restored clipboard from history', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous' });
+	desktop.getSelectionText();
+	t.deepEqual(desktop.clipboardHistory, ['Selected']);
+	desktop.getSelectionText({ excludeFromHistory: false });
+	t.deepEqual(desktop.clipboardHistory, ['Selected', 'Selected', 'Previous']);
+});
+
+fakeTest('getSelectionText throws CLIPBOARD_CONCEALED over a concealed clipboard with skipIfConcealed', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Password', clipboardConcealed: true });
+	const error = t.throws(() => desktop.getSelectionText({ skipIfConcealed: true }));
+	t.is(error.code, 'CLIPBOARD_CONCEALED');
+	t.is(desktop.copyCount, 0);
+	t.is(desktop.getSelectionText(), 'Selected');
+	t.is(desktop.clipboardText, 'Password');
+});
+
+fakeTest('getSelectionText throws APP_DENIED for a denied app without touching the clipboard', (t) => {
+	const focusedApp = { class: 'KeePassXC', instance: 'keepassxc', title: 'Passwords.kdbx - KeePassXC' };
+	const desktop = new FakeDesktop({ selection: 'Password', clipboardText: 'Previous', focusedApp });
+	const error = t.throws(() => desktop.getSelectionText({ denyApps: [{ class: 'keepassxc' }] }));
+	t.is(error.code, 'APP_DENIED');
+	t.is(desktop.copyCount, 0);
+	t.is(desktop.clipboardText, 'Previous');
+	t.deepEqual(desktop.clipboardHistory, []);
+	t.is(desktop.getSelectionText({ denyApps: [{ title: '^ssh ' }] }), 'Password');
+});
+
+fakeTest('getSelectionText throws APP_DENIED for an app not allowed', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', focusedApp: { exePath: '/usr/bin/gedit' } });
+	t.is(desktop.getSelectionText({ allowApps: [{ exePath: 'gedit' }] }), 'Selected');
+	const error = t.throws(() => desktop.getSelectionText({ allowApps: [{ exePath: 'code' }] }));
+	t.is(error.code, 'APP_DENIED');
+	const invalid = t.throws(() => desktop.getSelectionText({ denyApps: [{ title: '(' }] }));
+	t.is(invalid.code, 'INVALID_APP_MATCHER');
+});
+
+fakeTest('getSelectionText throws APP_DENIED for an app that cannot be told', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected' });
+	const error = t.throws(() => desktop.getSelectionText({ denyApps: [{ class: 'keepassxc' }] }));
+	t.is(error.code, 'APP_DENIED');
+	t.is(desktop.copyCount, 0);
+	t.is(desktop.getSelectionText(), 'Selected');
+});
+
+fakeTest('getSelection reads the primary selection without copying', (t) => {
+	const desktop = new FakeDesktop({ selection: 'Selected', clipboardText: 'Previous' });
+	const selection = desktop.getSelection({ strategy: 'primary' });
+	t.is(selection.text, 'Selected');
+	t.is(selection.strategy, 'primary');
+	t.is(desktop.copyCount, 0);
+});
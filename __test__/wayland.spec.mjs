@@ -0,0 +1,48 @@
+import test from 'ava';
+import { execFileSync } from 'child_process';
+
+import { getSelection, getSelectionText, getSelectionTextAsync } from '../index.js';
+
+/**
+ * These tests run against a real Wayland compositor, e.g. sway or weston in headless mode, and are
+ * skipped when there is none. `wl-copy` (from wl-clipboard) sets the primary selection, acting as
+ * the app the user highlights text in.
+ */
+const waylandTest = process.platform === 'linux' && process.env.WAYLAND_DISPLAY ? test.serial : test.skip;
+
+waylandTest('getSelectionText reads the primary selection', (t) => {
+	execFileSync('wl-copy', ['--primary', 'Hello from Wayland']);
+	t.is(getSelectionText({ strategy: 'primary' }), 'Hello from Wayland');
+});
+
+waylandTest('getSelectionText reads the primary selection by default', (t) => {
+	execFileSync('wl-copy', ['--primary', 'Default strategy']);
+	t.is(getSelectionText(), 'Default strategy');
+});
+
+waylandTest('getSelectionText leaves the clipboard untouched when reading the primary selection', (t) => {
+	execFileSync('wl-copy', ['Clipboard text']);
+	execFileSync('wl-copy', ['--primary', 'Primary text']);
+	t.is(getSelectionText({ strategy: 'primary' }), 'Primary text');
+	t.is(execFileSync('wl-paste', ['--no-newline']).toString(), 'Clipboard text');
+});
+
+waylandTest('getSelectionText returns an empty string when the primary selection is empty', (t) => {
+	execFileSync('wl-copy', ['--primary', '--clear']);
+	t.is(getSelectionText({ strategy: 'primary' }), '');
+});
+
+waylandTest('getSelectionTextAsync reads the primary selection', async (t) => {
+	execFileSync('wl-copy', ['--primary', 'Hello async']);
+	t.is(await getSelectionTextAsync({ strategy: 'primary' }), 'Hello async');
+});
+
+waylandTest('getSelection reads the primary selection with its formats', (t) => {
+	execFileSync('wl-copy', ['--primary', '--type', 'text/html', '<b>Bold</b>']);
+	const selection = getSelection({ strategy: 'primary' });
+	t.is(selection.html, '<b>Bold</b>');
+	t.is(selection.rtf, undefined);
+	t.true(selection.offeredFormats.includes('text/html'));
+	t.is(selection.strategy, 'primary');
+	t.true(selection.elapsedMs >= 0);
+});
@@ -3,20 +3,565 @@
 
 /* auto-generated by NAPI-RS */
 
+/**
+ * Description of an app for `denyApps` and `allowApps`, matched against the focused window. Every
+ * property that is set must match
+ */
+export interface AppMatcher {
+  /**
+  * The window class, matched (case insensitive) against the `WM_CLASS` instance and class names
+  * in Linux X11, the window class name in Windows, or the app bundle identifier and name in Mac,
+  * e.g. `'keepassxc'`
+  */
+  class?: string
+  /** A regular expression searched for in the window title, e.g. `'^ssh '` */
+  title?: string
+  /**
+  * The path of the app executable, e.g. `'/usr/bin/keepassxc'`, or only its file name, e.g.
+  * `'keepassxc'`
+  */
+  exePath?: string
+}
+/**
+ * Cancels the `typeText` calls in progress, which stop before their next chunk and reject with a
+ * `CANCELLED` error. The `typeText` calls made afterward are not affected.
+ */
+export function cancelTyping(): void
+/** The clipboard content reported by `onClipboardChange` */
+export interface ClipboardChange {
+  /** The clipboard text, which is `null` if the clipboard has no text, e.g. an image was copied */
+  text?: string
+  /** Every format the clipboard content is offered in, e.g. MIME types in Linux */
+  formats: Array<string>
+}
+/**
+ * What to do when the input focus moves to another window while the selection is being copied,
+ * e.g. the user switches windows right after triggering the copy, as the copied text could come
+ * from the wrong window (Linux X11 and Windows only)
+ */
+export const enum FocusChangePolicy {
+  /** Fail with a `FOCUS_CHANGED` error */
+  Abort = 'abort',
+  /**
+  * Copy the selection of the newly focused window instead, failing as with `Abort` if the focus
+  * keeps moving
+  */
+  Retry = 'retry',
+  /** Return the copied text anyway */
+  Ignore = 'ignore'
+}
+/**
+ * Returns the current selection along with its rich text formats and metadata. It performs the
+ * same single copy (or primary selection read in Linux) as `getSelectionText`, reading every
+ * format while the selection is on the clipboard, so the clipboard is only touched once.
+ *
+ * The returned object holds the selection `text`, its `html` and `rtf` if the source offered them,
+ * every format the source offered it in (`offeredFormats`), the `strategy` that retrieved it and
+ * the time it took (`elapsedMs`).
+ *
+ * ##### Arguments
+ * * `options` - Same as `getSelectionText`. The `formats` option sets which rich text formats to
+ *               read.
+ *
+ * ##### Errors
+ * Throws the same errors as `getSelectionText`. A rich text format that cannot be read is left
+ * out instead.
+ */
+export function getSelection(options?: number | GetSelectionTextOptions | undefined | null): SelectionResult
 /**
  * Returns the current selection text. If there is no selection text, returns an empty string.
  *
  * The selection text is retrieved in a 3 steps processes:
- * 1. Save clipboard existing text and clear clipboard
+ * 1. Save clipboard existing content (in every format, e.g. text, HTML, image) and clear clipboard
  * 2. Simulate `Ctrl + C` (`Cmd + C` in Mac) keyboard input to copy selection text to clipboard
- * 3. Read clipboard to retrieve selection text and return it as result
- * (the previous clipboard text is restored before returning to minimize side effects to users)
+ * 3. Poll clipboard until the copied selection text lands on it and return it as result
+ * (the previous clipboard content is restored before returning to minimize side effects to users,
+ * and the clipboard is cleared if it was empty)
+ *
+ * In Linux, the selection text is read from the primary selection by default instead (X11 or
+ * Wayland compositors supporting the data-control protocol), which holds the highlighted text
+ * already, so no keyboard input is simulated and the clipboard is not touched.
+ *
+ * ##### Arguments
+ * * `options` - An optional object to customize how the selection text is retrieved, see
+ *               `GetSelectionTextOptions`. For backward compatibility, a number can be passed
+ *               instead, which sets `copyWaitTimeMs`.
+ *
+ * ##### Errors
+ * Throws an `Error` whose `code` property is one of:
+ * * `CLIPBOARD_UNAVAILABLE` - The clipboard could not be opened or cleared
+ * * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created, e.g. missing
+ *                            accessibility permission in Mac
+ * * `KEY_SIMULATION_FAILED` - Simulating the copy keyboard input failed
+ * * `CLIPBOARD_RESTORE_FAILED` - The clipboard previous content could not be restored
+ * * `STRATEGY_UNSUPPORTED` - The selection strategy is not available on the current platform
+ * * `INVALID_SHORTCUT` - A `copyShortcut` description could not be parsed, e.g. unknown key name
+ * * `FOCUS_CHANGED` - The input focus moved to another window during the copy, see
+ *                     `focusChangePolicy`
+ * * `CLIPBOARD_CONCEALED` - The clipboard holds concealed content, e.g. a password, see
+ *                           `skipIfConcealed`
+ * * `APP_DENIED` - The focused app is denied, or not allowed, to retrieve the selection from, see
+ *                  `denyApps` and `allowApps`
+ * * `INVALID_APP_MATCHER` - A `denyApps` or `allowApps` app could not be parsed, e.g. its `title`
+ *                           is not a valid regular expression
+ * * `BUSY` - Another call is using the clipboard, see `failIfBusy`
+ */
+export function getSelectionText(options?: number | GetSelectionTextOptions | undefined | null): string
+/**
+ * Async version of `getSelectionText`. It performs the same process on a worker thread instead of
+ * the JS thread, so the event loop is not blocked while waiting for the copy to complete.
+ *
+ * Returns a promise that resolves with the selection text, or rejects with the same errors that
+ * `getSelectionText` throws.
+ *
+ * ##### Arguments
+ * * `options` - Same as `getSelectionText`
+ */
+export function getSelectionTextAsync(options?: number | GetSelectionTextOptions | undefined | null): Promise<string>
+/** Options to customize how `getSelectionText` retrieves the selection text */
+export interface GetSelectionTextOptions {
+  /**
+  * The minimum time to wait after performing the copy operation before reading the clipboard
+  * text. It defaults to 5ms.
+  */
+  copyWaitTimeMs?: number
+  /**
+  * The maximum time to wait for the copied selection text to land on the clipboard. If nothing
+  * is copied by then, e.g. there is no selection, an empty string is returned. It defaults to
+  * 100ms, which works for most use cases. However, a larger value would be needed to support use
+  * case for large selection text that takes longer to copy to the clipboard.
+  */
+  timeoutMs?: number
+  /**
+  * How often the clipboard is read while waiting for the copied selection text. It defaults to
+  * 5ms.
+  */
+  pollIntervalMs?: number
+  /**
+  * Whether to restore the clipboard previous content after the copy, which returns the clipboard
+  * to exactly its previous state (it is cleared if it was empty). If `false`, the copied
+  * selection text is kept on the clipboard instead. It defaults to `true`.
+  */
+  restoreClipboard?: boolean
+  /**
+  * How the selection text is retrieved: `'copy'` to copy it to the clipboard, or `'primary'` to
+  * read the primary selection (Linux only). It defaults to `'primary'` in Linux when the primary
+  * selection is readable and `'copy'` elsewhere.
+  */
+  strategy?: 'copy' | 'primary'
+  /** The maximum number of characters to return. Longer selection text is truncated. */
+  maxLength?: number
+  /**
+  * The rich text formats to read along with the text, only used by `getSelection`. It defaults to
+  * `['html', 'rtf']`.
+  */
+  formats?: Array<'html' | 'rtf'>
+  /**
+  * What to do with modifier keys the user is still holding down when the copy shortcut is
+  * simulated, e.g. `Alt + Shift` of the global hotkey that triggered the call, as they would turn
+  * `Ctrl + C` into `Ctrl + Alt + Shift + C`: `'release'` to release them before the copy and
+  * press them back after it if the user is still holding them down (which is only told in Mac
+  * and Linux X11, so they are left released in Windows and Wayland), `'wait'` to wait until the
+  * user releases them (they are released as with `'release'` if still held down after
+  * `modifierTimeoutMs`), or `'ignore'` to leave them as they are. It defaults to `'release'`.
+  */
+  modifierPolicy?: 'release' | 'wait' | 'ignore'
+  /**
+  * The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  * policy. It defaults to 1000ms.
+  */
+  modifierTimeoutMs?: number
+  /**
+  * The shortcut that copies the selection, as a key-chord description like `'Ctrl+Shift+C'`
+  * (e.g. for terminal emulators, where `Ctrl+C` interrupts the running program) or
+  * `'Ctrl+Insert'`, i.e. modifier keys (`Ctrl`, `Shift`, `Alt`, `Cmd` or `CmdOrCtrl`) followed by
+  * a key (a letter, digit, `F1` to `F12`, `Insert`, `Delete`, `Enter`, `Tab`, `Space` or `Escape`)
+  * joined with `+`. A list of shortcuts can be given to try them in order until one copies the
+  * selection, each waiting up to `timeoutMs`. It defaults to `'CmdOrCtrl+C'`.
+  */
+  copyShortcut?: string | Array<string>
+  /**
+  * What to do with the `'copy'` strategy when the focused window is a terminal emulator (e.g.
+  * xterm, GNOME Terminal, Konsole, Alacritty, kitty or WezTerm), where `Ctrl+C` interrupts the
+  * running program: `'primary'` to read the primary selection instead, `'shortcut'` to copy with
+  * `Ctrl+Shift+C` instead of `copyShortcut`, or `'ignore'` to copy as in any other window. The
+  * focused window is only told in Linux X11 sessions. It defaults to `'primary'`.
+  */
+  terminalPolicy?: 'primary' | 'shortcut' | 'ignore'
+  /**
+  * Names of terminal emulators to detect in addition to the known ones, matched (case
+  * insensitive) against the focused window `WM_CLASS` instance and class names and its process
+  * name
+  */
+  terminals?: Array<string>
+  /**
+  * What to do with the `'copy'` strategy when the input focus moves to another window during the
+  * copy, e.g. the user switches windows right after triggering it, as the copied text could come
+  * from the wrong window: `'abort'` to throw a `FOCUS_CHANGED` error, `'retry'` to copy the
+  * selection of the newly focused window instead (up to 2 more times, then it throws as with
+  * `'abort'`), or `'ignore'` to return the copied text anyway. The focused window is only told in
+  * Linux X11 and Windows. It defaults to `'abort'`.
+  */
+  focusChangePolicy?: 'abort' | 'retry' | 'ignore'
+  /**
+  * Whether to mark the restored clipboard content with the hints clipboard managers (e.g. Maccy,
+  * Windows clipboard history) check to not record it, so restoring the clipboard does not add a
+  * new history entry. In Linux, the only hint marks the content as a password, so the content is
+  * restored without it. It defaults to `true`.
+  */
+  excludeFromHistory?: boolean
+  /**
+  * Whether to throw a `CLIPBOARD_CONCEALED` error with the `'copy'` strategy, before any keyboard
+  * input is simulated, if the clipboard holds content marked concealed (e.g. a password copied
+  * from a password manager), instead of saving and restoring it. It defaults to `false`.
+  */
+  skipIfConcealed?: boolean
+  /**
+  * Apps to never retrieve the selection from, e.g. password managers, banking apps or terminal
+  * emulators running `ssh`. If the focused app matches one of them, an `APP_DENIED` error is
+  * thrown before the clipboard is touched or any keyboard input is simulated, as it is if the
+  * focused app cannot be told (e.g. in Linux Wayland sessions), so no denied app is missed.
+  */
+  denyApps?: Array<AppMatcher>
+  /**
+  * Apps to only retrieve the selection from. If the focused app matches none of them, or it
+  * cannot be told, an `APP_DENIED` error is thrown as with `denyApps`, which takes precedence.
+  * It defaults to allowing every app.
+  */
+  allowApps?: Array<AppMatcher>
+  /**
+  * Whether to throw a `BUSY` error right away if another call (e.g. from another hotkey or a
+  * worker thread) is using the clipboard, instead of waiting for it to finish, as only one call
+  * at a time saves, uses and restores the clipboard. It defaults to `false`.
+  */
+  failIfBusy?: boolean
+}
+/**
+ * What to do with modifier keys the user is still holding down (e.g. `Alt + Shift` of the global
+ * hotkey that triggered the call) when the copy shortcut is simulated, as they would turn it into
+ * a different shortcut
+ */
+export const enum ModifierPolicy {
+  /**
+  * Release them before the copy shortcut and press them back after it if the user is still
+  * holding them down, see [`press_back_modifiers`]
+  */
+  Release = 'release',
+  /**
+  * Wait until the user releases them, or release them as with `Release` if they are still held
+  * down after the timeout
+  */
+  Wait = 'wait',
+  /** Leave them as they are */
+  Ignore = 'ignore'
+}
+/**
+ * Listens to the clipboard changes, calling the callback with the new clipboard content each time
+ * the user (or an app) sets it, e.g. the user copies something. It relies on the X server
+ * notifying the clipboard owner changes (XFIXES extension) in X11, or on the data-control protocol
+ * in Wayland, so the clipboard is not polled.
+ *
+ * The transient clipboard changes `getSelectionText`, `getSelection` and `replaceSelectionText`
+ * cause while copying (or pasting) the selection (i.e. clearing the clipboard, copying the
+ * selection and restoring the clipboard) are not reported, as the clipboard changes to one of the
+ * last contents this process set are left out.
+ *
+ * Returns a `SelectionWatcher`, whose `stop()` stops listening. The process is kept alive until
+ * then.
  *
  * ##### Arguments
- * * `copyWaitTimeMs` - An optional number that sets how long to wait after performing the copy
- *                      operation before reading the clipboard text. It defaults to 5ms, which
- *                      works for most use cases with small selection text. However, a larger value
- *                      would be needed to support use case for large selection text that takes
- *                      longer to copy to the clipboard.
+ * * `callback` - Called with `(null, change)` for each clipboard change, where `change` holds the
+ *                clipboard `text` (`null` if it has no text) and every format it is offered in
+ *                (`formats`), or with an `Error` if the listening fails, which stops it
+ *
+ * ##### Errors
+ * Throws an `Error` whose `code` property is one of:
+ * * `STRATEGY_UNSUPPORTED` - Not in a Linux X11 or Wayland session, the X server does not support
+ *                            the XFIXES extension, or the Wayland compositor does not support the
+ *                            data-control protocol
+ * * `CLIPBOARD_UNAVAILABLE` - The X server or Wayland compositor could not be connected to
+ */
+export function onClipboardChange(callback: (err: Error | null, change: ClipboardChange) => void): SelectionWatcher
+/**
+ * Replaces the current selection with the text, e.g. the selection transformed (translated,
+ * proofread) after being retrieved with `getSelectionText`. The text is pasted in a 3 steps
+ * process:
+ * 1. Save clipboard existing content (in every format) and set the clipboard to the text
+ * 2. Simulate `Ctrl + V` (`Cmd + V` in Mac) keyboard input to paste the text over the selection
+ * 3. Wait for the focused app to read the clipboard and restore the clipboard previous content
+ *
+ * The modifier keys the user is holding down and the keyboard layout are handled as when copying
+ * the selection. In Linux X11, terminal emulators are pasted into with `Ctrl + Shift + V`.
+ *
+ * ##### Arguments
+ * * `text` - The text to replace the selection with
+ * * `options` - An optional object to customize how the text is pasted, see
+ *               `ReplaceSelectionTextOptions`
+ *
+ * ##### Errors
+ * Throws an `Error` whose `code` property is one of:
+ * * `CLIPBOARD_UNAVAILABLE` - The clipboard could not be opened or written
+ * * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created
+ * * `KEY_SIMULATION_FAILED` - Simulating the paste keyboard input failed
+ * * `CLIPBOARD_RESTORE_FAILED` - The clipboard previous content could not be restored
+ * * `INVALID_SHORTCUT` - The `pasteShortcut` description could not be parsed
+ * * `CLIPBOARD_CONCEALED` - The clipboard holds concealed content, see `skipIfConcealed`
+ * * `BUSY` - Another call is using the clipboard, see `failIfBusy`
+ */
+export function replaceSelectionText(text: string, options?: ReplaceSelectionTextOptions | undefined | null): void
+/** Options to customize how `replaceSelectionText` pastes the text */
+export interface ReplaceSelectionTextOptions {
+  /**
+  * The maximum time to wait after simulating the paste shortcut before restoring the clipboard,
+  * as the focused app reads the clipboard after handling the shortcut. In Linux, the clipboard
+  * is restored as soon as the app reads the pasted text (in Wayland, only with
+  * `excludeFromHistory`), while in Windows and Mac the whole time is waited for. If it is too
+  * short, the app may paste the restored clipboard content instead. It defaults to 200ms.
+  */
+  pasteWaitTimeMs?: number
+  /**
+  * Whether to restore the clipboard previous content after the paste. If `false`, the pasted
+  * text is kept on the clipboard instead. It defaults to `true`.
+  */
+  restoreClipboard?: boolean
+  /**
+  * What to do with modifier keys the user is still holding down when the paste shortcut is
+  * simulated, same as the `getSelectionText` option. It defaults to `'release'`.
+  */
+  modifierPolicy?: 'release' | 'wait' | 'ignore'
+  /**
+  * The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  * policy. It defaults to 1000ms.
+  */
+  modifierTimeoutMs?: number
+  /**
+  * The shortcut that pastes the clipboard, as a key-chord description like `'Shift+Insert'`, same
+  * as the `copyShortcut` option of `getSelectionText`. It defaults to `'CmdOrCtrl+V'`.
+  */
+  pasteShortcut?: string
+  /**
+  * What to do when the focused window is a terminal emulator, where `Ctrl+V` is sent to the
+  * running program: `'shortcut'` to paste with `Ctrl+Shift+V` instead of `pasteShortcut`, or
+  * `'ignore'` to paste as in any other window. The focused window is only told in Linux X11
+  * sessions. It defaults to `'shortcut'`.
+  */
+  terminalPolicy?: 'shortcut' | 'ignore'
+  /**
+  * Names of terminal emulators to detect in addition to the known ones, same as the
+  * `getSelectionText` option
+  */
+  terminals?: Array<string>
+  /**
+  * Whether to mark the pasted text and the restored clipboard content with the hints clipboard
+  * managers check to not record them, same as the `getSelectionText` option (in Linux, only the
+  * pasted text is marked). It defaults to `true`.
+  */
+  excludeFromHistory?: boolean
+  /**
+  * Whether to throw a `CLIPBOARD_CONCEALED` error, before any keyboard input is simulated, if the
+  * clipboard holds content marked concealed, same as the `getSelectionText` option. It defaults
+  * to `false`.
+  */
+  skipIfConcealed?: boolean
+  /**
+  * Whether to throw a `BUSY` error right away if another call is using the clipboard, instead of
+  * waiting for it to finish, same as the `getSelectionText` option. It defaults to `false`.
+  */
+  failIfBusy?: boolean
+}
+/** Rich text format of the selection content */
+export const enum SelectionFormat {
+  Html = 'html',
+  Rtf = 'rtf'
+}
+/** The selection content returned by `getSelection` */
+export interface SelectionResult {
+  /** The selection text, which is an empty string if there is no selection */
+  text: string
+  /** The selection HTML, if the source offered it */
+  html?: string
+  /** The selection RTF, if the source offered it */
+  rtf?: string
+  /**
+  * Every format the source offered the selection in, e.g. MIME types in Linux, UTIs in Mac and
+  * clipboard format names in Windows
+  */
+  offeredFormats: Array<string>
+  /** The strategy that retrieved the selection */
+  strategy: 'copy' | 'primary'
+  /** The time it took to retrieve the selection, in milliseconds */
+  elapsedMs: number
+  /**
+  * The application the selection was retrieved from, captured before any keyboard input is
+  * simulated. It is available in Windows, Mac and Linux X11 sessions, but not in Linux Wayland
+  * sessions, where the focused window is not exposed to other apps
+  */
+  source?: SelectionSource
+}
+/**
+ * The application the selection was retrieved from, i.e. the one whose window had the input focus
+ * right before the selection was retrieved
+ */
+export interface SelectionSource {
+  /** The window title. It is not told in Mac, where it needs the screen recording permission */
+  title?: string
+  /**
+  * The window class, e.g. `WM_CLASS` class name in Linux X11 (`"Gnome-terminal"`), the window
+  * class name in Windows (`"CASCADIA_HOSTING_WINDOW_CLASS"`) or the app bundle identifier in Mac
+  * (`"com.apple.Terminal"`)
+  */
+  class?: string
+  /**
+  * The window instance name, e.g. `WM_CLASS` instance name in Linux X11
+  * (`"gnome-terminal-server"`) or the app name in Mac (`"Terminal"`)
+  */
+  instance?: string
+  /** The id of the process that owns the window */
+  pid?: number
+  /** The path of the executable of the process that owns the window */
+  exePath?: string
+}
+/** Strategy used to retrieve the selection text */
+export const enum SelectionStrategy {
+  /** Copy the selection text to the clipboard by simulating `Ctrl + C` (`Cmd + C` in Mac) */
+  Copy = 'copy',
+  /**
+  * Read the primary selection, which holds the highlighted text in Linux (X11 or Wayland), without
+  * simulating any keyboard input or touching the clipboard
+  */
+  Primary = 'primary'
+}
+/**
+ * What to do when the window that has the input focus is a terminal emulator, where `Ctrl + C`
+ * interrupts the running program instead of copying the selection (Linux X11 only)
+ */
+export const enum TerminalPolicy {
+  /** Read the primary selection instead of copying the selection */
+  Primary = 'primary',
+  /** Copy the selection with `Ctrl + Shift + C` instead of the copy shortcuts */
+  Shortcut = 'shortcut',
+  /** Copy the selection as in any other window */
+  Ignore = 'ignore'
+}
+/**
+ * Types the text at the cursor of the focused app by simulating keyboard input, without touching
+ * the clipboard, e.g. to insert short text. It is performed on a worker thread, so the event loop
+ * is not blocked while typing.
+ *
+ * The text is typed in chunks of `chunkSize` characters, including characters the keyboard layout
+ * cannot produce (e.g. `é` with a US layout), which are typed through Unicode keyboard events in
+ * Windows and Mac, and by remapping a spare key in Linux. The modifier keys the user is holding
+ * down are handled as when copying the selection.
+ *
+ * Returns a promise that resolves once the whole text is typed.
+ *
+ * ##### Arguments
+ * * `text` - The text to type
+ * * `options` - An optional object to customize how the text is typed, see `TypeTextOptions`
+ *
+ * ##### Errors
+ * Rejects with an `Error` whose `code` property is one of:
+ * * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created
+ * * `KEY_SIMULATION_FAILED` - Simulating the keyboard input failed. The failed chunk is not typed
+ *                             again, as some of its characters may have been typed already
+ * * `CANCELLED` - `cancelTyping` was called before the whole text was typed
+ */
+export function typeText(text: string, options?: TypeTextOptions | undefined | null): Promise<void>
+/** Options to customize how `typeText` types the text */
+export interface TypeTextOptions {
+  /**
+  * The time to wait between chunks, e.g. for apps that drop keystrokes sent too fast. Set
+  * `chunkSize` to 1 to wait between every character. It defaults to 0ms.
+  */
+  delayMs?: number
+  /**
+  * The maximum number of characters typed at once. The typing can only be cancelled between
+  * chunks. It defaults to 50.
+  */
+  chunkSize?: number
+  /**
+  * What to do with modifier keys the user is still holding down when the text is typed, as they
+  * would turn the typed characters into shortcuts, same as the `getSelectionText` option. It
+  * defaults to `'release'`.
+  */
+  modifierPolicy?: 'release' | 'wait' | 'ignore'
+  /**
+  * The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  * policy. It defaults to 1000ms.
+  */
+  modifierTimeoutMs?: number
+}
+/**
+ * Watches the selection, calling the callback with the selection text each time it changes, e.g.
+ * the user highlights other text. It relies on the X server notifying the selection owner
+ * changes (XFIXES extension), so no keyboard input is simulated and the clipboard is not touched.
+ *
+ * The selection text is reported once it has not changed for `debounceMs`, so a selection being
+ * extended is reported once. Empty text and text equal to the last reported one are skipped.
+ *
+ * Returns a `SelectionWatcher`, whose `stop()` stops watching. The process is kept alive until
+ * then.
+ *
+ * ##### Arguments
+ * * `callback` - Called with `(null, text)` for each selection change, or with an `Error` (with
+ *                the same `code` property as the errors `getSelectionText` throws) if the
+ *                selection cannot be read. Watching stops if the X server connection is lost.
+ * * `options` - An optional object to customize how the changes are reported, see
+ *               `WatchSelectionOptions`
+ *
+ * ##### Errors
+ * Throws an `Error` whose `code` property is one of:
+ * * `STRATEGY_UNSUPPORTED` - Not in a Linux X11 session, or the X server does not support the
+ *                            XFIXES extension
+ * * `CLIPBOARD_UNAVAILABLE` - The X server could not be connected to
+ */
+export function watchSelection(callback: (err: Error | null, text: string) => void, options?: WatchSelectionOptions): SelectionWatcher
+/** Options to customize how `watchSelection` reports the selection changes */
+export interface WatchSelectionOptions {
+  /**
+  * How long the selection must stay unchanged before it is reported, so a selection being
+  * extended (e.g. the user dragging over text) is reported once. It defaults to 150ms.
+  */
+  debounceMs?: number
+  /** The maximum number of characters of the reported text. Longer selection text is truncated. */
+  maxLength?: number
+}
+/**
+ * Retrieves the selection text as `getSelectionText` does, with the options it is created with,
+ * but keeps the clipboard and keyboard connections open across reads instead of opening new ones
+ * each time, which makes each read faster. The connections are opened again if the display
+ * connection is lost.
  */
-export function getSelectionText(copyWaitTimeMs?: number | undefined | null): string
+export class SelectionReader {
+  /**
+  * ##### Arguments
+  * * `options` - Same as `getSelectionText`, used for every read
+  *
+  * ##### Errors
+  * Throws an `INVALID_SHORTCUT` or `INVALID_APP_MATCHER` error if the options cannot be parsed
+  */
+  constructor(options?: number | GetSelectionTextOptions | undefined | null)
+  /**
+  * Retrieves the current selection text, see `getSelectionText`.
+  *
+  * ##### Errors
+  * Throws the same errors as `getSelectionText`, or a `READER_CLOSED` error if the reader is
+  * closed
+  */
+  read(): string
+  /**
+  * Async version of `read`, see `getSelectionTextAsync`. Reads of the same reader run one at a
+  * time.
+  */
+  readAsync(): Promise<string>
+  /**
+  * Closes the clipboard and keyboard connections. Later reads throw a `READER_CLOSED` error.
+  * Calling it again does nothing.
+  */
+  close(): void
+}
+/** Handle of the watcher started by `watchSelection` or `onClipboardChange` */
+export class SelectionWatcher {
+  /** Stops watching the selection, which lets the process exit. Calling it again does nothing. */
+  stop(): void
+}
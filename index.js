@@ -281,6 +281,20 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { getSelectionText } = nativeBinding
+const { SelectionReader, SelectionWatcher, cancelTyping, getSelection, getSelectionText, getSelectionTextAsync, onClipboardChange, replaceSelectionText, typeText, watchSelection, FocusChangePolicy, ModifierPolicy, SelectionFormat, SelectionStrategy, TerminalPolicy } = nativeBinding
 
+module.exports.SelectionReader = SelectionReader
+module.exports.SelectionWatcher = SelectionWatcher
+module.exports.cancelTyping = cancelTyping
+module.exports.getSelection = getSelection
 module.exports.getSelectionText = getSelectionText
+module.exports.getSelectionTextAsync = getSelectionTextAsync
+module.exports.onClipboardChange = onClipboardChange
+module.exports.replaceSelectionText = replaceSelectionText
+module.exports.typeText = typeText
+module.exports.watchSelection = watchSelection
+module.exports.FocusChangePolicy = FocusChangePolicy
+module.exports.ModifierPolicy = ModifierPolicy
+module.exports.SelectionFormat = SelectionFormat
+module.exports.SelectionStrategy = SelectionStrategy
+module.exports.TerminalPolicy = TerminalPolicy
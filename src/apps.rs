@@ -0,0 +1,207 @@
+use regex::Regex;
+use std::path::Path;
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::source::SelectionSource;
+
+/// Description of an application, matched against the focused window. Every part that is set must
+/// match
+#[derive(Debug, Clone, Default)]
+pub struct AppPattern {
+  /// Name matched (case insensitive) against the window instance or class name
+  pub class: Option<String>,
+  /// Pattern searched for in the window title
+  pub title: Option<Regex>,
+  /// Path of the executable, or only its file name, matched against the path of the executable of
+  /// the process that owns the window
+  pub exe_path: Option<String>,
+}
+
+impl PartialEq for AppPattern {
+  fn eq(&self, other: &Self) -> bool {
+    self.class == other.class
+      && self.title.as_ref().map(Regex::as_str) == other.title.as_ref().map(Regex::as_str)
+      && self.exe_path == other.exe_path
+  }
+}
+
+impl AppPattern {
+  pub fn matches(&self, source: &SelectionSource) -> bool {
+    let matches_class = self.class.as_ref().is_none_or(|class| {
+      [&source.instance, &source.class]
+        .into_iter()
+        .flatten()
+        .any(|name| name.eq_ignore_ascii_case(class))
+    });
+    let matches_title = self.title.as_ref().is_none_or(|title| {
+      source
+        .title
+        .as_deref()
+        .is_some_and(|source_title| title.is_match(source_title))
+    });
+    let matches_exe_path = self.exe_path.as_ref().is_none_or(|exe_path| {
+      source.exe_path.as_deref().is_some_and(|source_path| {
+        source_path == exe_path
+          || Path::new(source_path)
+            .file_name()
+            .is_some_and(|file_name| file_name == exe_path.as_str())
+      })
+    });
+    matches_class && matches_title && matches_exe_path
+  }
+}
+
+/// Applications the selection is allowed or denied to be retrieved from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppRules {
+  /// Applications the selection may only be retrieved from, or `None` to allow any application
+  /// that is not denied
+  pub allow: Option<Vec<AppPattern>>,
+  /// Applications the selection must not be retrieved from, which takes precedence over `allow`
+  pub deny: Vec<AppPattern>,
+}
+
+impl AppRules {
+  pub fn is_empty(&self) -> bool {
+    self.allow.is_none() && self.deny.is_empty()
+  }
+
+  /// Fails with [`ErrorCode::AppDenied`] if the selection must not be retrieved from the focused
+  /// application. If the focused application cannot be told while there are rules, it is denied,
+  /// as it cannot be checked against them
+  pub fn check(&self, source: Option<&SelectionSource>) -> Result<()> {
+    let Some(source) = source else {
+      return match self.is_empty() {
+        true => Ok(()),
+        false => Err(Error::new(
+          ErrorCode::AppDenied,
+          "The focused app cannot be told, so it cannot be checked against the app rules"
+            .to_string(),
+        )),
+      };
+    };
+    if self.deny.iter().any(|pattern| pattern.matches(source)) {
+      return Err(Error::new(
+        ErrorCode::AppDenied,
+        format!("The focused app {} is denied", describe(source)),
+      ));
+    }
+    match &self.allow {
+      Some(allow) if !allow.iter().any(|pattern| pattern.matches(source)) => Err(Error::new(
+        ErrorCode::AppDenied,
+        format!("The focused app {} is not allowed", describe(source)),
+      )),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// Names the application in error messages by its class or executable, leaving the window title
+/// out as it may hold private data
+fn describe(source: &SelectionSource) -> String {
+  source
+    .class
+    .as_ref()
+    .or(source.instance.as_ref())
+    .or(source.exe_path.as_ref())
+    .map_or_else(|| "(unknown)".to_string(), |name| format!("\"{name}\""))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn keepassxc() -> SelectionSource {
+    SelectionSource {
+      title: Some("Passwords.kdbx - KeePassXC".to_string()),
+      class: Some("KeePassXC".to_string()),
+      instance: Some("keepassxc".to_string()),
+      pid: Some(4242),
+      exe_path: Some("/usr/bin/keepassxc".to_string()),
+    }
+  }
+
+  fn ssh_terminal() -> SelectionSource {
+    SelectionSource {
+      title: Some("ssh admin@db.example.com".to_string()),
+      class: Some("XTerm".to_string()),
+      instance: Some("xterm".to_string()),
+      pid: Some(4343),
+      exe_path: Some("/usr/bin/xterm".to_string()),
+    }
+  }
+
+  #[test]
+  fn matches_every_part_set() {
+    let class = AppPattern {
+      class: Some("keepassxc".to_string()),
+      ..Default::default()
+    };
+    assert!(class.matches(&keepassxc()));
+    assert!(!class.matches(&ssh_terminal()));
+
+    let title = AppPattern {
+      title: Some(Regex::new(r"^ssh\b").unwrap()),
+      ..Default::default()
+    };
+    assert!(title.matches(&ssh_terminal()));
+    assert!(!title.matches(&keepassxc()));
+
+    for exe_path in ["/usr/bin/keepassxc", "keepassxc"] {
+      let pattern = AppPattern {
+        exe_path: Some(exe_path.to_string()),
+        ..Default::default()
+      };
+      assert!(pattern.matches(&keepassxc()), "{exe_path}");
+    }
+
+    let both = AppPattern {
+      class: Some("xterm".to_string()),
+      title: Some(Regex::new("ssh").unwrap()),
+      ..Default::default()
+    };
+    assert!(both.matches(&ssh_terminal()));
+    let mut local_terminal = ssh_terminal();
+    local_terminal.title = Some("~/src".to_string());
+    assert!(!both.matches(&local_terminal));
+  }
+
+  #[test]
+  fn denies_matching_apps() {
+    let rules = AppRules {
+      deny: vec![AppPattern {
+        class: Some("KeePassXC".to_string()),
+        ..Default::default()
+      }],
+      ..Default::default()
+    };
+    let err = rules.check(Some(&keepassxc())).unwrap_err();
+    assert_eq!(err.status, ErrorCode::AppDenied);
+    assert!(!err.reason.contains("Passwords.kdbx"));
+    assert!(rules.check(Some(&ssh_terminal())).is_ok());
+    let err = rules.check(None).unwrap_err();
+    assert_eq!(err.status, ErrorCode::AppDenied);
+    assert!(AppRules::default().check(None).is_ok());
+  }
+
+  #[test]
+  fn allows_only_listed_apps() {
+    let rules = AppRules {
+      allow: Some(vec![AppPattern {
+        class: Some("xterm".to_string()),
+        ..Default::default()
+      }]),
+      deny: vec![AppPattern {
+        title: Some(Regex::new("ssh").unwrap()),
+        ..Default::default()
+      }],
+    };
+    let mut local_terminal = ssh_terminal();
+    local_terminal.title = Some("~/src".to_string());
+    assert!(rules.check(Some(&local_terminal)).is_ok());
+    for source in [Some(&keepassxc()), Some(&ssh_terminal()), None] {
+      let err = rules.check(source).unwrap_err();
+      assert_eq!(err.status, ErrorCode::AppDenied);
+    }
+  }
+}
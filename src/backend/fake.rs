@@ -0,0 +1,789 @@
+use enigo::Direction::{self, Click, Press, Release};
+use std::{
+  sync::{Arc, Mutex, MutexGuard},
+  thread,
+  time::{Duration, Instant},
+};
+
+use super::{ClipboardBackend, DesktopBackend, KeystrokeBackend};
+use crate::error::{Error, ErrorCode, Result};
+use crate::formats::{SelectionFormat, SelectionFormats};
+use crate::keycode;
+use crate::selection::Selection;
+use crate::shortcut::Shortcut;
+use crate::source::SelectionSource;
+
+pub const TEXT_MIME_TYPE: &str = "text/plain";
+pub const HTML_MIME_TYPE: &str = "text/html";
+pub const RTF_MIME_TYPE: &str = "text/rtf";
+/// MIME type password managers set to `secret` along with a copied password, see
+/// [`FakeDesktop::set_concealed_clipboard_text`]
+pub const PASSWORD_MANAGER_HINT_MIME_TYPE: &str = "x-kde-passwordManagerHint";
+
+/// Clipboard content, as (MIME type, data) pairs
+pub type FakeContent = Vec<(String, String)>;
+
+#[derive(Debug)]
+struct FakeState {
+  clipboard: FakeContent,
+  /// Clipboard contents a clipboard manager recorded, i.e. the ones not excluded from history
+  history: Vec<FakeContent>,
+  selection: FakeContent,
+  copy_delay: Duration,
+  paste_delay: Duration,
+  failing_keycode: Option<u16>,
+  failing_restore: bool,
+  held_keycodes: Vec<u16>,
+  pending_copy: Option<Instant>,
+  pending_paste: Option<Instant>,
+  /// Keys the user is holding down, and when they release them
+  user_held_keycodes: Vec<u16>,
+  user_release: Option<Instant>,
+  /// Whether the keys the user physically holds down can be told apart from the simulated ones
+  physical_key_state: bool,
+  copy_count: u32,
+  paste_count: u32,
+  /// Shortcuts the focused app copies its selection on
+  app_copy_shortcuts: Vec<Shortcut>,
+  /// Shortcuts the focused app pastes the clipboard on, replacing its selection
+  app_paste_shortcuts: Vec<Shortcut>,
+  focused_window: u64,
+  /// App the focused window belongs to, which cannot be told if `None`
+  focused_app: Option<SelectionSource>,
+  /// Windows the user switches the focus to the next times the app copies its selection
+  focus_switches: Vec<u64>,
+  /// Text typed at the cursor of the focused app
+  typed_text: String,
+  typed_chunks: u32,
+  /// Number of characters typed after which typing fails, like an input injection failing midway
+  typing_failure_after: Option<usize>,
+}
+
+impl Default for FakeState {
+  fn default() -> Self {
+    FakeState {
+      clipboard: Vec::new(),
+      history: Vec::new(),
+      selection: Vec::new(),
+      copy_delay: Duration::ZERO,
+      paste_delay: Duration::ZERO,
+      failing_keycode: None,
+      failing_restore: false,
+      held_keycodes: Vec::new(),
+      pending_copy: None,
+      pending_paste: None,
+      user_held_keycodes: Vec::new(),
+      user_release: None,
+      physical_key_state: true,
+      copy_count: 0,
+      paste_count: 0,
+      app_copy_shortcuts: vec![Shortcut::copy()],
+      app_paste_shortcuts: vec![Shortcut::paste()],
+      focused_window: 1,
+      focused_app: None,
+      focus_switches: Vec::new(),
+      typed_text: String::new(),
+      typed_chunks: 0,
+      typing_failure_after: None,
+    }
+  }
+}
+
+impl FakeState {
+  /// Lands the pending copy on the clipboard (and the pending paste on the selection) once the
+  /// copy (or paste) delay has elapsed, and releases the keys the user is holding down once they
+  /// release them
+  fn settle(&mut self) {
+    let now = Instant::now();
+    if self.pending_copy.is_some_and(|at| now >= at) {
+      self.pending_copy = None;
+      self.clipboard = self.selection.clone();
+      // The focused app does not mark its copy for clipboard managers
+      if !is_concealed(&self.clipboard) {
+        self.history.push(self.clipboard.clone());
+      }
+    }
+    if self.pending_paste.is_some_and(|at| now >= at) {
+      // Like real apps, the focused app reads the clipboard when it handles the paste, not when the
+      // paste shortcut is pressed
+      self.pending_paste = None;
+      self.selection = self.clipboard.clone();
+    }
+    if self.user_release.is_some_and(|at| now >= at) {
+      self.user_release = None;
+      let user_held_keycodes = std::mem::take(&mut self.user_held_keycodes);
+      self
+        .held_keycodes
+        .retain(|keycode| !user_held_keycodes.contains(keycode));
+    }
+  }
+
+  /// Returns whether pressing the key with the currently held keys is one of the shortcuts
+  fn is_shortcut(&self, shortcuts: &[Shortcut], keycode: u16) -> bool {
+    shortcuts.iter().any(|shortcut| {
+      shortcut.key == keycode
+        && shortcut.modifiers.len() == self.held_keycodes.len()
+        && shortcut
+          .modifiers
+          .iter()
+          .all(|modifier| self.held_keycodes.contains(modifier))
+    })
+  }
+
+  fn copy(&mut self) {
+    self.copy_count += 1;
+    if !self.focus_switches.is_empty() {
+      self.focused_window = self.focus_switches.remove(0);
+    }
+    // Like real apps, the focused app leaves the clipboard untouched if there is no selection
+    if !self.selection.is_empty() {
+      self.pending_copy = Some(Instant::now() + self.copy_delay);
+    }
+  }
+
+  fn paste(&mut self) {
+    self.paste_count += 1;
+    self.pending_paste = Some(Instant::now() + self.paste_delay);
+  }
+}
+
+/// An in-memory desktop whose focused app copies its selection to the clipboard when the copy
+/// shortcut is pressed, after the copy delay, like a real app does
+#[derive(Clone, Default)]
+pub struct FakeDesktop {
+  state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeDesktop {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn state(&self) -> MutexGuard<'_, FakeState> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  pub fn clipboard(&self) -> FakeClipboard {
+    FakeClipboard {
+      desktop: self.clone(),
+    }
+  }
+
+  pub fn keyboard(&self) -> FakeKeystroke {
+    FakeKeystroke {
+      desktop: self.clone(),
+    }
+  }
+
+  /// Returns the clipboard and keyboard along with the focused app and the primary selection, to
+  /// run the whole retrieval of the selection on
+  pub fn backends(&self) -> FakeBackends {
+    FakeBackends {
+      desktop: self.clone(),
+      clipboard: self.clipboard(),
+      keyboard: self.keyboard(),
+    }
+  }
+
+  /// Sets the content the focused app has selected, which is empty if there is no selection
+  pub fn set_selection(&self, content: FakeContent) {
+    self.state().selection = content;
+  }
+
+  #[cfg(test)]
+  pub fn set_selection_text(&self, text: &str) {
+    self.set_selection(vec![(TEXT_MIME_TYPE.to_string(), text.to_string())]);
+  }
+
+  pub fn set_clipboard(&self, content: FakeContent) {
+    self.write_clipboard(content, true);
+  }
+
+  /// Sets the clipboard content, which the clipboard manager records unless it is excluded from
+  /// history or empty
+  fn write_clipboard(&self, content: FakeContent, exclude_from_history: bool) {
+    let mut state = self.state();
+    // Land the pending paste with the clipboard content it is due with
+    state.settle();
+    if !exclude_from_history && !content.is_empty() && !is_concealed(&content) {
+      state.history.push(content.clone());
+    }
+    state.clipboard = content;
+  }
+
+  /// Sets the clipboard text marked concealed, like a password manager does with a copied password
+  pub fn set_concealed_clipboard_text(&self, text: &str) {
+    self.set_clipboard(vec![
+      (TEXT_MIME_TYPE.to_string(), text.to_string()),
+      (
+        PASSWORD_MANAGER_HINT_MIME_TYPE.to_string(),
+        "secret".to_string(),
+      ),
+    ]);
+  }
+
+  /// Sets the time the focused app takes to copy its selection to the clipboard
+  pub fn set_copy_delay(&self, copy_delay: Duration) {
+    self.state().copy_delay = copy_delay;
+  }
+
+  /// Sets the time the focused app takes to read the clipboard once the paste shortcut is pressed
+  pub fn set_paste_delay(&self, paste_delay: Duration) {
+    self.state().paste_delay = paste_delay;
+  }
+
+  /// Makes the user hold the keys with the given physical keycodes down, until they release them
+  /// after `release_after` if set
+  #[cfg(test)]
+  pub fn hold_keys(&self, keycodes: &[u16], release_after: Option<Duration>) {
+    let mut state = self.state();
+    state.held_keycodes.extend_from_slice(keycodes);
+    state.user_held_keycodes = keycodes.to_vec();
+    state.user_release = release_after.map(|release_after| Instant::now() + release_after);
+  }
+
+  /// Sets whether the keys the user physically holds down can be told apart from the simulated
+  /// ones, which is only the case in Mac
+  #[cfg(test)]
+  pub fn set_physical_key_state(&self, physical_key_state: bool) {
+    self.state().physical_key_state = physical_key_state;
+  }
+
+  /// Returns the physical keycodes of the keys that are currently held down
+  #[cfg(test)]
+  pub fn held_keys(&self) -> Vec<u16> {
+    let mut state = self.state();
+    state.settle();
+    state.held_keycodes.clone()
+  }
+
+  /// Sets the shortcuts the focused app copies its selection on, e.g. `Ctrl + Shift + C` in
+  /// terminal emulators. It defaults to `Ctrl + C` (`Cmd + C` in Mac)
+  pub fn set_app_copy_shortcuts(&self, shortcuts: Vec<Shortcut>) {
+    self.state().app_copy_shortcuts = shortcuts;
+  }
+
+  /// Sets the shortcuts the focused app pastes the clipboard on, e.g. `Ctrl + Shift + V` in
+  /// terminal emulators. It defaults to `Ctrl + V` (`Cmd + V` in Mac)
+  pub fn set_app_paste_shortcuts(&self, shortcuts: Vec<Shortcut>) {
+    self.state().app_paste_shortcuts = shortcuts;
+  }
+
+  /// Makes the user switch the focus to the given windows, one the next times the app copies its
+  /// selection, i.e. right after the copy shortcut is pressed
+  pub fn set_focus_switches(&self, windows: Vec<u64>) {
+    self.state().focus_switches = windows;
+  }
+
+  /// Sets the app the focused window belongs to, which cannot be told if `None`
+  pub fn set_focused_app(&self, focused_app: Option<SelectionSource>) {
+    self.state().focused_app = focused_app;
+  }
+
+  /// Makes every keystroke of the key with the given physical keycode fail
+  pub fn set_failing_keycode(&self, keycode: Option<u16>) {
+    self.state().failing_keycode = keycode;
+  }
+
+  /// Makes every restore of the clipboard saved content fail
+  #[cfg(test)]
+  pub fn set_failing_restore(&self, failing_restore: bool) {
+    self.state().failing_restore = failing_restore;
+  }
+
+  pub fn clipboard_content(&self) -> FakeContent {
+    let mut state = self.state();
+    state.settle();
+    state.clipboard.clone()
+  }
+
+  pub fn clipboard_text(&self) -> Option<String> {
+    find(&self.clipboard_content(), TEXT_MIME_TYPE)
+  }
+
+  /// Returns the number of times the copy shortcut was pressed
+  pub fn copy_count(&self) -> u32 {
+    self.state().copy_count
+  }
+
+  /// Returns the text the focused app has selected, which is replaced by the pasted text
+  pub fn selection_text(&self) -> Option<String> {
+    let mut state = self.state();
+    state.settle();
+    find(&state.selection, TEXT_MIME_TYPE)
+  }
+
+  /// Returns the number of times the paste shortcut was pressed
+  pub fn paste_count(&self) -> u32 {
+    self.state().paste_count
+  }
+
+  /// Returns the texts of the clipboard contents the clipboard manager recorded, oldest first
+  pub fn clipboard_history(&self) -> Vec<String> {
+    let mut state = self.state();
+    state.settle();
+    state
+      .history
+      .iter()
+      .filter_map(|content| find(content, TEXT_MIME_TYPE))
+      .collect()
+  }
+
+  /// Returns the text typed at the cursor of the focused app
+  pub fn typed_text(&self) -> String {
+    self.state().typed_text.clone()
+  }
+
+  /// Returns the number of times text was typed
+  #[cfg(test)]
+  pub fn typed_chunks(&self) -> u32 {
+    self.state().typed_chunks
+  }
+
+  /// Makes typing fail once the given number of characters are typed, after typing them
+  #[cfg(test)]
+  pub fn set_typing_failure_after(&self, characters: Option<usize>) {
+    self.state().typing_failure_after = characters;
+  }
+}
+
+fn is_concealed(content: &FakeContent) -> bool {
+  find(content, PASSWORD_MANAGER_HINT_MIME_TYPE).as_deref() == Some("secret")
+}
+
+fn find(content: &FakeContent, mime_type: &str) -> Option<String> {
+  content
+    .iter()
+    .find(|(content_mime_type, _)| content_mime_type == mime_type)
+    .map(|(_, data)| data.clone())
+}
+
+/// Clipboard of a [`FakeDesktop`]
+pub struct FakeClipboard {
+  desktop: FakeDesktop,
+}
+
+impl ClipboardBackend for FakeClipboard {
+  type Snapshot = FakeContent;
+
+  fn capture(&mut self) -> Result<FakeContent> {
+    Ok(self.desktop.clipboard_content())
+  }
+
+  fn is_concealed(&self, snapshot: &FakeContent) -> bool {
+    is_concealed(snapshot)
+  }
+
+  fn restore(&mut self, snapshot: FakeContent, exclude_from_history: bool) -> Result<()> {
+    if self.desktop.state().failing_restore {
+      return Err(Error::new(
+        ErrorCode::ClipboardRestoreFailed,
+        "Failed to restore the fake clipboard".to_string(),
+      ));
+    }
+    self.desktop.write_clipboard(snapshot, exclude_from_history);
+    Ok(())
+  }
+
+  fn clear(&mut self) -> Result<()> {
+    self.desktop.set_clipboard(Vec::new());
+    Ok(())
+  }
+
+  fn write_text(
+    &mut self,
+    text: &str,
+    exclude_from_history: bool,
+    _until_read: bool,
+  ) -> Result<()> {
+    self.desktop.write_clipboard(
+      vec![(TEXT_MIME_TYPE.to_string(), text.to_string())],
+      exclude_from_history,
+    );
+    Ok(())
+  }
+
+  /// The text is read once the focused app handles the pending paste
+  fn wait_for_read(&mut self, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+      let is_read = {
+        let mut state = self.desktop.state();
+        state.settle();
+        state.pending_paste.is_none()
+      };
+      if is_read || Instant::now() >= deadline {
+        return;
+      }
+      thread::sleep(Duration::from_millis(1));
+    }
+  }
+
+  fn read_text(&mut self) -> Option<String> {
+    self.desktop.clipboard_text()
+  }
+
+  fn read_formats(&mut self, formats: &[SelectionFormat]) -> SelectionFormats {
+    read_formats(&self.desktop.clipboard_content(), formats)
+  }
+}
+
+/// Reads the formats the content is offered in, along with the given rich text formats
+fn read_formats(content: &FakeContent, formats: &[SelectionFormat]) -> SelectionFormats {
+  let read = |format, mime_type| {
+    formats
+      .contains(&format)
+      .then(|| find(content, mime_type))
+      .flatten()
+  };
+  SelectionFormats {
+    offered: content
+      .iter()
+      .map(|(mime_type, _)| mime_type.clone())
+      .collect(),
+    html: read(SelectionFormat::Html, HTML_MIME_TYPE),
+    rtf: read(SelectionFormat::Rtf, RTF_MIME_TYPE),
+  }
+}
+
+/// Keyboard of a [`FakeDesktop`], whose focused app copies the selection (or pastes the clipboard)
+/// when one of its copy (or paste) shortcuts is pressed, i.e. its key is pressed while exactly its
+/// modifier keys are held down. Its keyboard layout only produces ASCII characters
+pub struct FakeKeystroke {
+  desktop: FakeDesktop,
+}
+
+impl KeystrokeBackend for FakeKeystroke {
+  fn raw(&mut self, keycode: u16, direction: Direction) -> Result<()> {
+    let mut state = self.desktop.state();
+    state.settle();
+    if state.failing_keycode == Some(keycode) {
+      return Err(Error::new(
+        ErrorCode::KeySimulationFailed,
+        format!("Simulated failure of keycode {keycode}"),
+      ));
+    }
+    if direction != Release {
+      if state.is_shortcut(&state.app_copy_shortcuts, keycode) {
+        state.copy();
+      } else if state.is_shortcut(&state.app_paste_shortcuts, keycode) {
+        state.paste();
+      }
+    }
+    match direction {
+      Press => state.held_keycodes.push(keycode),
+      Release => state.held_keycodes.retain(|held| *held != keycode),
+      Click => {}
+    }
+    Ok(())
+  }
+
+  /// Types the characters one by one like a real input injection, so a failure leaves the ones
+  /// before typed
+  fn text(&mut self, text: &str) -> Result<()> {
+    let mut state = self.desktop.state();
+    state.typed_chunks += 1;
+    for character in text.chars() {
+      let failed = state
+        .typing_failure_after
+        .is_some_and(|after| state.typed_text.chars().count() >= after);
+      if failed {
+        return Err(Error::new(
+          ErrorCode::KeySimulationFailed,
+          format!("Failed to type {character:?}"),
+        ));
+      }
+      state.typed_text.push(character);
+    }
+    Ok(())
+  }
+
+  fn held_modifiers(&mut self) -> Result<Vec<u16>> {
+    let mut state = self.desktop.state();
+    state.settle();
+    Ok(
+      state
+        .held_keycodes
+        .iter()
+        .copied()
+        .filter(|keycode| keycode::MODIFIERS.contains(keycode))
+        .collect(),
+    )
+  }
+
+  fn physically_held_modifiers(&mut self) -> Option<Vec<u16>> {
+    let mut state = self.desktop.state();
+    state.settle();
+    if !state.physical_key_state {
+      return None;
+    }
+    Some(
+      state
+        .user_held_keycodes
+        .iter()
+        .copied()
+        .filter(|keycode| keycode::MODIFIERS.contains(keycode))
+        .collect(),
+    )
+  }
+
+  fn focused_window(&mut self) -> Option<u64> {
+    Some(self.desktop.state().focused_window)
+  }
+}
+
+/// Clipboard and keyboard of a [`FakeDesktop`], along with its focused app and its primary
+/// selection, which is the selection of the focused app
+pub struct FakeBackends {
+  desktop: FakeDesktop,
+  clipboard: FakeClipboard,
+  keyboard: FakeKeystroke,
+}
+
+impl DesktopBackend for FakeBackends {
+  type Clipboard = FakeClipboard;
+  type Keystroke = FakeKeystroke;
+
+  fn backends(&mut self) -> Result<(&mut FakeClipboard, &mut FakeKeystroke)> {
+    Ok((&mut self.clipboard, &mut self.keyboard))
+  }
+
+  fn active_source(&mut self) -> Option<SelectionSource> {
+    self.desktop.state().focused_app.clone()
+  }
+
+  fn primary_selection(
+    &mut self,
+    formats: &[SelectionFormat],
+    read_formats: bool,
+  ) -> Result<Selection> {
+    let mut state = self.desktop.state();
+    state.settle();
+    Ok(Selection {
+      text: find(&state.selection, TEXT_MIME_TYPE).unwrap_or_default(),
+      formats: if read_formats {
+        self::read_formats(&state.selection, formats)
+      } else {
+        SelectionFormats::default()
+      },
+      ..Default::default()
+    })
+  }
+}
+
+#[cfg(feature = "fake")]
+mod js {
+  use napi::Either;
+  use std::time::{Duration, Instant};
+
+  use super::{FakeDesktop, HTML_MIME_TYPE, TEXT_MIME_TYPE};
+  use crate::error::Result;
+  use crate::options::{GetSelectionTextOptions, ReplaceSelectionTextOptions, TypeTextOptions};
+  use crate::replace;
+  use crate::result::SelectionResult;
+  use crate::selection::{self, SelectionOptions, SelectionStrategy};
+  use crate::source::SelectionSource;
+  use crate::typing;
+
+  /// Options of the in-memory `FakeDesktop`
+  #[napi(object)]
+  pub struct FakeDesktopOptions {
+    /// The text the focused app has selected. It defaults to no selection.
+    pub selection: Option<String>,
+    /// The HTML the focused app copies along with the selection text
+    pub selection_html: Option<String>,
+    /// The clipboard text before the copy. It defaults to an empty clipboard.
+    pub clipboard_text: Option<String>,
+    /// Whether the clipboard text is marked concealed, like a password copied from a password
+    /// manager. It defaults to `false`.
+    pub clipboard_concealed: Option<bool>,
+    /// The time the focused app takes to copy the selection to the clipboard. It defaults to 0ms.
+    pub copy_delay_ms: Option<u32>,
+    /// Whether simulating the copy shortcut fails. It defaults to `false`.
+    pub fail_copy: Option<bool>,
+    /// The shortcut the focused app copies the selection on, e.g. `'Ctrl+Shift+C'` for a terminal
+    /// emulator. It defaults to `'CmdOrCtrl+C'`.
+    pub app_copy_shortcut: Option<String>,
+    /// The number of times the user switches the focus to another window right after the copy
+    /// shortcut is pressed. It defaults to 0.
+    pub focus_switches: Option<u32>,
+    /// The time the focused app takes to read the clipboard once the paste shortcut is pressed. It
+    /// defaults to 0ms.
+    pub paste_delay_ms: Option<u32>,
+    /// The shortcut the focused app pastes the clipboard on. It defaults to `'CmdOrCtrl+V'`.
+    pub app_paste_shortcut: Option<String>,
+    /// The focused app, checked against the `denyApps` and `allowApps` options. It defaults to an
+    /// app that cannot be told.
+    pub focused_app: Option<SelectionSource>,
+  }
+
+  /// Resolves the options as `getSelectionText` does, except that the strategy defaults to
+  /// `'copy'` instead of `'primary'` in Linux, as the fake desktop simulates the copy whatever the
+  /// desktop it runs on
+  fn resolve_options(
+    options: Option<Either<u32, GetSelectionTextOptions>>,
+  ) -> Result<SelectionOptions> {
+    let options = match options {
+      Some(Either::A(copy_wait_time_ms)) => GetSelectionTextOptions {
+        copy_wait_time_ms: Some(copy_wait_time_ms),
+        ..Default::default()
+      },
+      Some(Either::B(options)) => options,
+      None => GetSelectionTextOptions::default(),
+    };
+    GetSelectionTextOptions {
+      strategy: Some(options.strategy.unwrap_or(SelectionStrategy::Copy)),
+      ..options
+    }
+    .try_into()
+  }
+
+  /// An in-memory desktop to test the copy process without touching the real clipboard or keyboard.
+  /// Its focused app copies the selection to the clipboard `copyDelayMs` after the copy shortcut is
+  /// pressed. Only available in builds with the `fake` feature.
+  #[napi(js_name = "FakeDesktop")]
+  pub struct JsFakeDesktop {
+    desktop: FakeDesktop,
+  }
+
+  #[napi]
+  impl JsFakeDesktop {
+    #[napi(constructor)]
+    pub fn new(options: Option<FakeDesktopOptions>) -> Result<Self> {
+      let desktop = FakeDesktop::new();
+      if let Some(options) = options {
+        let mut selection = Vec::new();
+        if let Some(text) = options.selection {
+          selection.push((TEXT_MIME_TYPE.to_string(), text));
+        }
+        if let Some(html) = options.selection_html {
+          selection.push((HTML_MIME_TYPE.to_string(), html));
+        }
+        desktop.set_selection(selection);
+        match options.clipboard_text {
+          Some(text) if options.clipboard_concealed.unwrap_or(false) => {
+            desktop.set_concealed_clipboard_text(&text)
+          }
+          Some(text) => desktop.set_clipboard(vec![(TEXT_MIME_TYPE.to_string(), text)]),
+          None => {}
+        }
+        desktop.set_copy_delay(Duration::from_millis(u64::from(
+          options.copy_delay_ms.unwrap_or(0),
+        )));
+        if options.fail_copy.unwrap_or(false) {
+          desktop.set_failing_keycode(Some(crate::keycode::C));
+        }
+        if let Some(description) = options.app_copy_shortcut {
+          desktop.set_app_copy_shortcuts(vec![description.parse()?]);
+        }
+        desktop.set_paste_delay(Duration::from_millis(u64::from(
+          options.paste_delay_ms.unwrap_or(0),
+        )));
+        if let Some(description) = options.app_paste_shortcut {
+          desktop.set_app_paste_shortcuts(vec![description.parse()?]);
+        }
+        desktop.set_focus_switches(
+          (2..)
+            .take(options.focus_switches.unwrap_or(0) as usize)
+            .collect(),
+        );
+        desktop.set_focused_app(options.focused_app);
+      }
+      Ok(JsFakeDesktop { desktop })
+    }
+
+    /// Same as `getSelectionText`, run on this desktop, whose primary selection is the selection
+    /// of its focused app. The strategy defaults to `'copy'` on any platform.
+    #[napi]
+    pub fn get_selection_text(
+      &self,
+      options: Option<Either<u32, GetSelectionTextOptions>>,
+    ) -> Result<String> {
+      let options = resolve_options(options)?;
+      selection::get_selection_text_with(&mut self.desktop.backends(), &options)
+    }
+
+    /// Same as `getSelection`, run on this desktop, whose primary selection is the selection of
+    /// its focused app. The strategy defaults to `'copy'` on any platform.
+    #[napi]
+    pub fn get_selection(
+      &self,
+      options: Option<Either<u32, GetSelectionTextOptions>>,
+    ) -> Result<SelectionResult> {
+      let start = Instant::now();
+      let options = resolve_options(options)?;
+      let (selection, strategy) =
+        selection::get_selection(&mut self.desktop.backends(), &options, true)?;
+      Ok(SelectionResult::new(
+        selection,
+        strategy,
+        start.elapsed().as_secs_f64() * 1000.0,
+      ))
+    }
+
+    /// Same as `replaceSelectionText`, run on this desktop. Terminal emulators are not detected,
+    /// i.e. the `terminalPolicy` option is ignored.
+    #[napi]
+    pub fn replace_selection_text(
+      &self,
+      text: String,
+      options: Option<ReplaceSelectionTextOptions>,
+    ) -> Result<()> {
+      replace::paste_text(
+        &mut self.desktop.clipboard(),
+        &mut self.desktop.keyboard(),
+        &text,
+        &options.unwrap_or_default().try_into()?,
+      )
+    }
+
+    /// Same as `typeText`, run on this desktop synchronously, so it cannot be cancelled. Its
+    /// keyboard layout only produces ASCII characters.
+    #[napi]
+    pub fn type_text(&self, text: String, options: Option<TypeTextOptions>) -> Result<()> {
+      typing::type_text_with(
+        &mut self.desktop.keyboard(),
+        &text,
+        &options.unwrap_or_default().into(),
+        || false,
+      )
+    }
+
+    /// The text typed at the cursor of the focused app
+    #[napi(getter)]
+    pub fn typed_text(&self) -> String {
+      self.desktop.typed_text()
+    }
+
+    /// The text the focused app has selected, which is `null` if there is no selection
+    #[napi(getter)]
+    pub fn selection_text(&self) -> Option<String> {
+      self.desktop.selection_text()
+    }
+
+    /// The clipboard text, which is `null` if the clipboard has no text
+    #[napi(getter)]
+    pub fn clipboard_text(&self) -> Option<String> {
+      self.desktop.clipboard_text()
+    }
+
+    /// The number of times the copy shortcut was pressed
+    #[napi(getter)]
+    pub fn copy_count(&self) -> u32 {
+      self.desktop.copy_count()
+    }
+
+    /// The texts of the clipboard contents a clipboard manager recorded, oldest first, i.e. the
+    /// copied selection and the clipboard writes not excluded from history
+    #[napi(getter)]
+    pub fn clipboard_history(&self) -> Vec<String> {
+      self.desktop.clipboard_history()
+    }
+
+    /// The number of times the paste shortcut was pressed
+    #[napi(getter)]
+    pub fn paste_count(&self) -> u32 {
+      self.desktop.paste_count()
+    }
+  }
+}
@@ -0,0 +1,103 @@
+//! Backends the clipboard and the keyboard are driven through when the selection is copied to the
+//! clipboard (or replaced by pasting), so the copy logic in [`crate::selection`] (and the paste
+//! logic in [`crate::replace`]) does not depend on the actual desktop.
+//!
+//! * [`SystemClipboard`] and [`SystemKeystroke`] - The real clipboard and keyboard input, which
+//!   [`SystemBackends`] keeps across copies
+//! * [`fake::FakeDesktop`] - An in-memory desktop whose focused app copies a configured selection
+//!   after a configurable delay (and records the pasted and typed text), available in tests and
+//!   with the `fake` feature
+//!
+//! [`DesktopBackend`] bundles a clipboard and a keyboard with the focused app and the primary
+//! selection, which the whole retrieval of the selection in [`crate::selection::get_selection`]
+//! runs on
+
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+mod system;
+
+pub use system::{SystemBackends, SystemClipboard, SystemKeystroke};
+
+use enigo::Direction;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::formats::{SelectionFormat, SelectionFormats};
+use crate::selection::Selection;
+use crate::source::SelectionSource;
+
+/// Clipboard the selection is copied to
+pub trait ClipboardBackend {
+  /// Clipboard content saved before the copy and written back after it
+  type Snapshot;
+
+  /// Saves the clipboard content in every format it is offered in
+  fn capture(&mut self) -> Result<Self::Snapshot>;
+
+  /// Returns whether the saved content was marked concealed by the app that set it, e.g. a
+  /// password manager
+  fn is_concealed(&self, snapshot: &Self::Snapshot) -> bool;
+
+  /// Writes the saved content back to the clipboard, or clears the clipboard if it had no content.
+  /// With `exclude_from_history`, the content is marked for clipboard managers to not record it
+  /// (except in Linux, where the only marker means the content is a secret)
+  fn restore(&mut self, snapshot: Self::Snapshot, exclude_from_history: bool) -> Result<()>;
+
+  fn clear(&mut self) -> Result<()>;
+
+  /// Sets the clipboard content to the text to paste, marked as with [`ClipboardBackend::restore`].
+  /// With `until_read`, the text may stop being served once read, as the clipboard is restored
+  /// right after
+  fn write_text(&mut self, text: &str, exclude_from_history: bool, until_read: bool) -> Result<()>;
+
+  /// Waits until the focused app reads the text set by [`ClipboardBackend::write_text`], or the
+  /// timeout elapses if the read cannot be told
+  fn wait_for_read(&mut self, timeout: Duration);
+
+  /// Reads the clipboard text, which is `None` if the clipboard has no text
+  fn read_text(&mut self) -> Option<String>;
+
+  /// Reads the formats the clipboard content is offered in, along with the given rich text formats
+  fn read_formats(&mut self, formats: &[SelectionFormat]) -> SelectionFormats;
+}
+
+/// Desktop the selection is retrieved from: its clipboard and keyboard input, the application that
+/// has the input focus and the primary selection
+pub trait DesktopBackend {
+  type Clipboard: ClipboardBackend;
+  type Keystroke: KeystrokeBackend;
+
+  /// Returns the clipboard and keyboard input the selection is copied with
+  fn backends(&mut self) -> Result<(&mut Self::Clipboard, &mut Self::Keystroke)>;
+
+  /// Returns the application whose window has the input focus, or `None` if it cannot be told
+  fn active_source(&mut self) -> Option<SelectionSource>;
+
+  /// Reads the primary selection, along with the given rich text formats with `read_formats`
+  fn primary_selection(
+    &mut self,
+    formats: &[SelectionFormat],
+    read_formats: bool,
+  ) -> Result<Selection>;
+}
+
+/// Keyboard the copy (or paste) shortcut is simulated on, and the text is typed with
+pub trait KeystrokeBackend {
+  /// Simulates the key by its physical keycode, see [`crate::keycode`]
+  fn raw(&mut self, keycode: u16, direction: Direction) -> Result<()>;
+
+  /// Types the text, including the characters the keyboard layout cannot produce. If it fails,
+  /// some of the characters may have been typed already, so the text must not be typed again
+  fn text(&mut self, text: &str) -> Result<()>;
+
+  /// Returns the physical keycodes of the modifier keys that are currently held down
+  fn held_modifiers(&mut self) -> Result<Vec<u16>>;
+
+  /// Returns the physical keycodes of the modifier keys the user is physically holding down,
+  /// regardless of the simulated keyboard input, or `None` if it cannot be told
+  fn physically_held_modifiers(&mut self) -> Option<Vec<u16>>;
+
+  /// Returns an identifier of the window that receives the keyboard input, or `None` if it cannot
+  /// be told
+  fn focused_window(&mut self) -> Option<u64>;
+}
@@ -0,0 +1,544 @@
+use arboard::Clipboard;
+use enigo::Direction;
+use enigo::{Enigo, Keyboard, Settings};
+use std::{thread, time::Duration};
+
+use super::{ClipboardBackend, DesktopBackend, KeystrokeBackend};
+use crate::error::{ErrorCode, Result, WithErrorCode};
+use crate::formats::{SelectionFormat, SelectionFormats};
+#[cfg(any(windows, target_os = "macos", target_os = "linux"))]
+use crate::keycode;
+#[cfg(target_os = "linux")]
+use crate::linux::{
+  self,
+  x11::{X11Connection, XTestKeyboard},
+};
+use crate::selection::Selection;
+use crate::snapshot::ClipboardSnapshot;
+#[cfg(not(target_os = "linux"))]
+use crate::source;
+use crate::source::SelectionSource;
+#[cfg(target_os = "linux")]
+use crate::watch;
+
+/// The system clipboard. The clipboard content is read and written through the arboard clipboard
+/// and, in Linux X11 sessions, the connection to the X server it keeps, so reads do not connect
+/// again each time
+pub struct SystemClipboard {
+  clipboard: Clipboard,
+  #[cfg(target_os = "linux")]
+  x11: Option<X11Connection>,
+  /// Read of the text set to paste, by the app it is pasted into
+  #[cfg(target_os = "linux")]
+  pending_read: Option<linux::PendingRead>,
+}
+
+impl SystemClipboard {
+  pub fn new() -> Result<Self> {
+    Ok(SystemClipboard {
+      clipboard: Clipboard::new().with_code(ErrorCode::ClipboardUnavailable)?,
+      #[cfg(target_os = "linux")]
+      x11: linux::connect_x11()?,
+      #[cfg(target_os = "linux")]
+      pending_read: None,
+    })
+  }
+
+  /// Returns whether the connection to the display still works. In Linux X11 sessions, the
+  /// connection to the X server is checked with a round trip
+  #[cfg(target_os = "linux")]
+  pub fn is_connected(&self) -> bool {
+    self.x11.as_ref().is_none_or(X11Connection::is_connected)
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub fn is_connected(&self) -> bool {
+    true
+  }
+
+  /// Returns the application whose window has the input focus, or `None` if it cannot be told. In
+  /// Linux, it is read through the connection to the X server the clipboard keeps, which only
+  /// exists in X11 sessions, as Wayland does not expose the focused window to other clients
+  #[cfg(target_os = "linux")]
+  pub fn active_source(&self) -> Option<SelectionSource> {
+    linux::active_window_source(self.x11.as_ref()?)
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub fn active_source(&self) -> Option<SelectionSource> {
+    source::active_source()
+  }
+
+  fn write_text_with_arboard(&mut self, text: &str, exclude_from_history: bool) -> Result<()> {
+    let set = self.clipboard.set();
+    let set = if exclude_from_history {
+      crate::snapshot::exclude_from_history(set)
+    } else {
+      set
+    };
+    set.text(text).with_code(ErrorCode::ClipboardUnavailable)?;
+    self.remember_own_content();
+    Ok(())
+  }
+
+  #[cfg(target_os = "linux")]
+  fn remember_own_content(&mut self) {
+    watch::remember_own_clipboard_content(&mut self.clipboard, self.x11.as_ref());
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn remember_own_content(&mut self) {}
+}
+
+impl ClipboardBackend for SystemClipboard {
+  type Snapshot = ClipboardSnapshot;
+
+  #[cfg(target_os = "linux")]
+  fn capture(&mut self) -> Result<ClipboardSnapshot> {
+    ClipboardSnapshot::capture(&mut self.clipboard, self.x11.as_ref())
+  }
+
+  #[cfg(any(windows, target_os = "macos"))]
+  fn capture(&mut self) -> Result<ClipboardSnapshot> {
+    ClipboardSnapshot::capture()
+  }
+
+  #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+  fn capture(&mut self) -> Result<ClipboardSnapshot> {
+    ClipboardSnapshot::capture(&mut self.clipboard, false)
+  }
+
+  fn is_concealed(&self, snapshot: &ClipboardSnapshot) -> bool {
+    snapshot.is_concealed()
+  }
+
+  #[cfg(any(windows, target_os = "macos"))]
+  fn restore(&mut self, snapshot: ClipboardSnapshot, exclude_from_history: bool) -> Result<()> {
+    snapshot.restore(exclude_from_history)
+  }
+
+  /// The content is written back without the history exclusion marker, which is the concealment
+  /// marker of password managers in Linux, unless it had it
+  #[cfg(not(any(windows, target_os = "macos")))]
+  fn restore(&mut self, snapshot: ClipboardSnapshot, _exclude_from_history: bool) -> Result<()> {
+    snapshot.restore(&mut self.clipboard)?;
+    self.remember_own_content();
+    Ok(())
+  }
+
+  fn clear(&mut self) -> Result<()> {
+    self
+      .clipboard
+      .clear()
+      .with_code(ErrorCode::ClipboardUnavailable)?;
+    self.remember_own_content();
+    Ok(())
+  }
+
+  #[cfg(target_os = "linux")]
+  fn write_text(&mut self, text: &str, exclude_from_history: bool, until_read: bool) -> Result<()> {
+    self.pending_read = linux::write_pasted_text(text, exclude_from_history, until_read)?;
+    if self.pending_read.is_some() {
+      self.remember_own_content();
+      return Ok(());
+    }
+    self.write_text_with_arboard(text, exclude_from_history)
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn write_text(
+    &mut self,
+    text: &str,
+    exclude_from_history: bool,
+    _until_read: bool,
+  ) -> Result<()> {
+    self.write_text_with_arboard(text, exclude_from_history)
+  }
+
+  /// In X11 and Wayland, the requests of the text are seen when it is served by this process (see
+  /// [`linux::write_pasted_text`]), otherwise the whole timeout is waited for
+  #[cfg(target_os = "linux")]
+  fn wait_for_read(&mut self, timeout: Duration) {
+    match self.pending_read.take() {
+      Some(read) => read.wait(timeout),
+      None => thread::sleep(timeout),
+    }
+  }
+
+  /// Windows and Mac do not tell when the clipboard content is read, so the whole timeout is
+  /// waited for, which is expected to be enough for the focused app to handle the paste
+  #[cfg(not(target_os = "linux"))]
+  fn wait_for_read(&mut self, timeout: Duration) {
+    thread::sleep(timeout);
+  }
+
+  /// The text read is the selection the focused app copied, which is remembered as set by this
+  /// process too, as it is copied on its behalf
+  fn read_text(&mut self) -> Option<String> {
+    let text = self.clipboard.get_text().ok()?;
+    self.remember_own_content();
+    Some(text)
+  }
+
+  #[cfg(target_os = "linux")]
+  fn read_formats(&mut self, formats: &[SelectionFormat]) -> SelectionFormats {
+    crate::formats::read_clipboard_formats(formats, self.x11.as_ref())
+  }
+
+  #[cfg(any(windows, target_os = "macos"))]
+  fn read_formats(&mut self, formats: &[SelectionFormat]) -> SelectionFormats {
+    crate::formats::read_clipboard_formats(formats)
+  }
+
+  #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+  fn read_formats(&mut self, _formats: &[SelectionFormat]) -> SelectionFormats {
+    SelectionFormats::default()
+  }
+}
+
+/// The system keyboard input, simulated with enigo. In Linux, the keys are simulated through XTEST
+/// instead, as enigo cannot simulate physical keys with libxdo, and enigo is only created to type
+/// text
+pub struct SystemKeystroke {
+  #[cfg(not(target_os = "linux"))]
+  enigo: Enigo,
+  #[cfg(target_os = "linux")]
+  enigo: Option<Enigo>,
+  #[cfg(target_os = "linux")]
+  xtest: XTestKeyboard,
+}
+
+// In Mac, enigo holds a `CGEventSource`, which is not marked `Send` although Quartz event sources
+// can be used from any thread. It is moved to the worker thread of `SelectionReader.readAsync`,
+// where it is only used behind the reader lock, i.e. by one thread at a time
+#[cfg(target_os = "macos")]
+unsafe impl Send for SystemKeystroke {}
+
+impl SystemKeystroke {
+  #[cfg(not(target_os = "linux"))]
+  pub fn new() -> Result<Self> {
+    Ok(SystemKeystroke {
+      enigo: Enigo::new(&Settings::default()).with_code(ErrorCode::KeyboardInitFailed)?,
+    })
+  }
+
+  #[cfg(target_os = "linux")]
+  pub fn new() -> Result<Self> {
+    Ok(SystemKeystroke {
+      enigo: None,
+      xtest: XTestKeyboard::new()?,
+    })
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn enigo(&mut self) -> Result<&mut Enigo> {
+    Ok(&mut self.enigo)
+  }
+
+  /// Returns whether the connection to the display still works. In Linux, the XTEST connection is
+  /// checked with a round trip, elsewhere there is no connection to lose
+  #[cfg(target_os = "linux")]
+  pub fn is_connected(&self) -> bool {
+    self.xtest.is_connected()
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub fn is_connected(&self) -> bool {
+    true
+  }
+
+  #[cfg(target_os = "linux")]
+  fn enigo(&mut self) -> Result<&mut Enigo> {
+    if self.enigo.is_none() {
+      self.enigo = Some(Enigo::new(&Settings::default()).with_code(ErrorCode::KeyboardInitFailed)?);
+    }
+    Ok(self.enigo.as_mut().expect("enigo was just created"))
+  }
+}
+
+/// The system clipboard and keyboard input, each created on first use and kept for the next
+/// copies, which saves opening new display connections each time. They are created again once the
+/// connection to the display is lost. The keyboard input is only created to copy the selection,
+/// so reading the primary selection or the focused app does not need it (e.g. in Wayland sessions
+/// without an X server)
+#[derive(Default)]
+pub struct SystemBackends {
+  clipboard: Option<SystemClipboard>,
+  keyboard: Option<SystemKeystroke>,
+}
+
+impl SystemBackends {
+  pub fn get(&mut self) -> Result<(&mut SystemClipboard, &mut SystemKeystroke)> {
+    if self
+      .keyboard
+      .as_ref()
+      .is_some_and(|keyboard| !keyboard.is_connected())
+    {
+      self.keyboard = None;
+    }
+    if self.keyboard.is_none() {
+      self.keyboard = Some(SystemKeystroke::new()?);
+    }
+    let clipboard = Self::kept_clipboard(&mut self.clipboard)?;
+    let keyboard = self
+      .keyboard
+      .as_mut()
+      .expect("the keyboard was just created");
+    Ok((clipboard, keyboard))
+  }
+
+  /// Returns the clipboard alone, without creating the keyboard input
+  pub fn clipboard(&mut self) -> Result<&mut SystemClipboard> {
+    Self::kept_clipboard(&mut self.clipboard)
+  }
+
+  fn kept_clipboard(clipboard: &mut Option<SystemClipboard>) -> Result<&mut SystemClipboard> {
+    if clipboard
+      .as_ref()
+      .is_some_and(|clipboard| !clipboard.is_connected())
+    {
+      *clipboard = None;
+    }
+    match clipboard {
+      Some(clipboard) => Ok(clipboard),
+      clipboard => Ok(clipboard.insert(SystemClipboard::new()?)),
+    }
+  }
+
+  /// Drops the clipboard and keyboard input, so they are created again on next use
+  pub fn reset(&mut self) {
+    self.clipboard = None;
+    self.keyboard = None;
+  }
+}
+
+impl DesktopBackend for SystemBackends {
+  type Clipboard = SystemClipboard;
+  type Keystroke = SystemKeystroke;
+
+  fn backends(&mut self) -> Result<(&mut SystemClipboard, &mut SystemKeystroke)> {
+    self.get()
+  }
+
+  fn active_source(&mut self) -> Option<SelectionSource> {
+    self.clipboard().ok()?.active_source()
+  }
+
+  #[cfg(target_os = "linux")]
+  fn primary_selection(
+    &mut self,
+    formats: &[SelectionFormat],
+    read_formats: bool,
+  ) -> Result<Selection> {
+    // In Wayland, the primary selection is read through the data-control protocol, without the
+    // clipboard
+    let mut clipboard = match linux::is_wayland_session() {
+      true => None,
+      false => Some(self.clipboard()?),
+    };
+    let text = match &mut clipboard {
+      Some(clipboard) => linux::x11::get_primary_selection_text(&mut clipboard.clipboard)?,
+      None => linux::wayland::get_primary_selection_text()?,
+    };
+    let x11 = clipboard.and_then(|clipboard| clipboard.x11.as_ref());
+    let formats = if read_formats {
+      crate::formats::read_primary_formats(formats, x11)
+    } else {
+      SelectionFormats::default()
+    };
+    Ok(Selection {
+      text,
+      formats,
+      ..Default::default()
+    })
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  fn primary_selection(
+    &mut self,
+    _formats: &[SelectionFormat],
+    _read_formats: bool,
+  ) -> Result<Selection> {
+    Err(crate::error::Error::new(
+      ErrorCode::StrategyUnsupported,
+      "The primary selection is only available in Linux".to_string(),
+    ))
+  }
+}
+
+impl KeystrokeBackend for SystemKeystroke {
+  #[cfg(not(target_os = "linux"))]
+  fn raw(&mut self, keycode: u16, direction: Direction) -> Result<()> {
+    self
+      .enigo
+      .raw(keycode, direction)
+      .with_code(ErrorCode::KeySimulationFailed)
+  }
+
+  #[cfg(target_os = "linux")]
+  fn raw(&mut self, keycode: u16, direction: Direction) -> Result<()> {
+    let keycode = u8::try_from(keycode).with_code(ErrorCode::KeySimulationFailed)?;
+    if matches!(direction, Direction::Press | Direction::Click) {
+      self.xtest.key(keycode, true)?;
+    }
+    if matches!(direction, Direction::Release | Direction::Click) {
+      self.xtest.key(keycode, false)?;
+    }
+    Ok(())
+  }
+
+  /// enigo types any character: through Unicode keyboard events in Windows and Mac, and by
+  /// remapping a spare keycode to it in Linux
+  fn text(&mut self, text: &str) -> Result<()> {
+    self
+      .enigo()?
+      .text(text)
+      .with_code(ErrorCode::KeySimulationFailed)
+  }
+
+  #[cfg(windows)]
+  fn held_modifiers(&mut self) -> Result<Vec<u16>> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+      GetAsyncKeyState, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_RCONTROL, VK_RMENU,
+      VK_RSHIFT, VK_RWIN,
+    };
+
+    // Virtual keys of `keycode::MODIFIERS`, in the same order
+    let virtual_keys = [
+      VK_LSHIFT,
+      VK_RSHIFT,
+      VK_LCONTROL,
+      VK_RCONTROL,
+      VK_LMENU,
+      VK_RMENU,
+      VK_LWIN,
+      VK_RWIN,
+    ];
+    Ok(
+      keycode::MODIFIERS
+        .into_iter()
+        .zip(virtual_keys)
+        // The most significant bit is set if the key is down
+        .filter(|(_, virtual_key)| unsafe { GetAsyncKeyState(i32::from(*virtual_key)) } < 0)
+        .map(|(keycode, _)| keycode)
+        .collect(),
+    )
+  }
+
+  #[cfg(target_os = "macos")]
+  fn held_modifiers(&mut self) -> Result<Vec<u16>> {
+    use objc2_core_graphics::{CGEventSource, CGEventSourceStateID};
+
+    Ok(
+      keycode::MODIFIERS
+        .into_iter()
+        .filter(|keycode| CGEventSource::key_state(CGEventSourceStateID::HIDSystemState, *keycode))
+        .collect(),
+    )
+  }
+
+  #[cfg(target_os = "linux")]
+  fn held_modifiers(&mut self) -> Result<Vec<u16>> {
+    // X keycodes fit in a byte
+    let modifiers = keycode::MODIFIERS.map(|keycode| keycode as u8);
+    Ok(
+      self
+        .xtest
+        .held_keys(&modifiers)?
+        .into_iter()
+        .map(u16::from)
+        .collect(),
+    )
+  }
+
+  #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+  fn held_modifiers(&mut self) -> Result<Vec<u16>> {
+    Ok(Vec::new())
+  }
+
+  /// The hardware key state, which simulated keyboard input does not change
+  #[cfg(target_os = "macos")]
+  fn physically_held_modifiers(&mut self) -> Option<Vec<u16>> {
+    self.held_modifiers().ok()
+  }
+
+  /// The key state of the physical keyboard devices, which is only told in X11 sessions, as
+  /// Wayland does not expose the keyboard state to other clients
+  #[cfg(target_os = "linux")]
+  fn physically_held_modifiers(&mut self) -> Option<Vec<u16>> {
+    if !crate::linux::is_x11_session() {
+      return None;
+    }
+    let modifiers = keycode::MODIFIERS.map(|keycode| keycode as u8);
+    let held_keys = self.xtest.physically_held_keys(&modifiers).ok()?;
+    Some(held_keys.into_iter().map(u16::from).collect())
+  }
+
+  /// `GetAsyncKeyState` includes the simulated keyboard input, so a modifier key released by
+  /// simulation reads as up while the user still holds it (telling them apart would take a
+  /// low-level keyboard hook running for the whole process)
+  #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+  fn physically_held_modifiers(&mut self) -> Option<Vec<u16>> {
+    None
+  }
+
+  #[cfg(windows)]
+  fn focused_window(&mut self) -> Option<u64> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    let window = unsafe { GetForegroundWindow() };
+    (!window.is_null()).then_some(window as u64)
+  }
+
+  /// The window is only told in X11 sessions, as Wayland does not expose the focused window to
+  /// other clients
+  #[cfg(target_os = "linux")]
+  fn focused_window(&mut self) -> Option<u64> {
+    if !crate::linux::is_x11_session() {
+      return None;
+    }
+    self.xtest.active_window().map(u64::from)
+  }
+
+  #[cfg(not(any(windows, target_os = "linux")))]
+  fn focused_window(&mut self) -> Option<u64> {
+    None
+  }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use std::env;
+
+  use super::*;
+  use crate::linux::x11;
+
+  /// Reads the focused app, the primary selection and the clipboard (saved and restored) over and
+  /// over, and checks no connection to the X server is opened again for them. It sets the
+  /// clipboard content of the X session, so it is ignored by default like the X11 tests
+  #[test]
+  #[ignore = "sets the clipboard content of the X session"]
+  fn reads_through_kept_connections() {
+    assert!(env::var_os("DISPLAY").is_some(), "no X server to run on");
+    let formats = [SelectionFormat::Html, SelectionFormat::Rtf];
+    let read = |backends: &mut SystemBackends| {
+      backends.active_source();
+      backends.primary_selection(&formats, true).unwrap();
+      let (clipboard, _) = backends.get().unwrap();
+      let snapshot = clipboard.capture().unwrap();
+      clipboard.is_concealed(&snapshot);
+      clipboard.read_text();
+      clipboard.read_formats(&formats);
+      clipboard.restore(snapshot, true).unwrap();
+    };
+    let mut backends = SystemBackends::default();
+    // The first read connects the backends (and starts the selection server)
+    read(&mut backends);
+
+    let connections = x11::connection_count();
+    for _ in 0..3 {
+      read(&mut backends);
+    }
+    assert_eq!(x11::connection_count(), connections);
+  }
+}
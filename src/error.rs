@@ -0,0 +1,76 @@
+use napi::{Env, JsError};
+use std::fmt::Display;
+
+/// Error codes that are set as the `code` property of errors thrown to JS, so callers can tell
+/// failures apart without parsing error messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+  /// The clipboard could not be opened or cleared
+  ClipboardUnavailable,
+  /// The keyboard input simulator could not be created
+  KeyboardInitFailed,
+  /// Simulating the copy (or paste, or typing) keyboard input failed
+  KeySimulationFailed,
+  /// The clipboard previous content could not be written back
+  ClipboardRestoreFailed,
+  /// The requested selection strategy is not available on the current platform
+  StrategyUnsupported,
+  /// A copy shortcut description could not be parsed
+  InvalidShortcut,
+  /// The input focus moved to another window while the selection was being copied
+  FocusChanged,
+  /// The typing was cancelled before all the text was typed
+  Cancelled,
+  /// The clipboard holds content marked concealed, e.g. a password copied from a password manager
+  ClipboardConcealed,
+  /// The focused app is denied, or not allowed, to retrieve the selection from
+  AppDenied,
+  /// An app matcher could not be parsed, e.g. its title is not a valid regular expression
+  InvalidAppMatcher,
+  /// The selection reader was closed
+  ReaderClosed,
+  /// Another call is using the clipboard, with the option to fail instead of waiting for it
+  Busy,
+}
+
+impl AsRef<str> for ErrorCode {
+  fn as_ref(&self) -> &str {
+    match self {
+      ErrorCode::ClipboardUnavailable => "CLIPBOARD_UNAVAILABLE",
+      ErrorCode::KeyboardInitFailed => "KEYBOARD_INIT_FAILED",
+      ErrorCode::KeySimulationFailed => "KEY_SIMULATION_FAILED",
+      ErrorCode::ClipboardRestoreFailed => "CLIPBOARD_RESTORE_FAILED",
+      ErrorCode::StrategyUnsupported => "STRATEGY_UNSUPPORTED",
+      ErrorCode::InvalidShortcut => "INVALID_SHORTCUT",
+      ErrorCode::FocusChanged => "FOCUS_CHANGED",
+      ErrorCode::Cancelled => "CANCELLED",
+      ErrorCode::ClipboardConcealed => "CLIPBOARD_CONCEALED",
+      ErrorCode::AppDenied => "APP_DENIED",
+      ErrorCode::InvalidAppMatcher => "INVALID_APP_MATCHER",
+      ErrorCode::ReaderClosed => "READER_CLOSED",
+      ErrorCode::Busy => "BUSY",
+    }
+  }
+}
+
+pub type Error = napi::Error<ErrorCode>;
+pub type Result<T> = napi::Result<T, ErrorCode>;
+
+pub trait WithErrorCode<T> {
+  /// Maps the error to an [`Error`] with the given code, keeping the original error message
+  fn with_code(self, code: ErrorCode) -> Result<T>;
+}
+
+impl<T, E: Display> WithErrorCode<T> for std::result::Result<T, E> {
+  fn with_code(self, code: ErrorCode) -> Result<T> {
+    self.map_err(|err| Error::new(code, err.to_string()))
+  }
+}
+
+/// Converts an [`Error`] into a `napi::Error` that keeps its code when thrown or rejected to JS.
+///
+/// This is needed where napi only accepts its own error type, e.g. `Task::resolve`, which would
+/// otherwise replace the code with a generic napi status
+pub fn into_napi_error(env: Env, err: Error) -> napi::Error {
+  napi::Error::from(JsError::from(err).into_unknown(env))
+}
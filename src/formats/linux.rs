@@ -0,0 +1,101 @@
+use super::{SelectionFormat, SelectionFormats};
+use crate::{
+  error::Result,
+  linux::{
+    self,
+    wayland::{self, ClipboardType},
+    x11::X11Connection,
+  },
+};
+
+const HTML_MIME_TYPE: &str = "text/html";
+const RTF_MIME_TYPES: [&str; 3] = ["text/rtf", "application/rtf", "text/richtext"];
+
+/// Selection to read the formats from
+#[derive(Clone, Copy)]
+enum Selection {
+  Clipboard,
+  Primary,
+}
+
+/// Reads the formats through the connection to the X server in X11 sessions, or through the
+/// data-control protocol in Wayland sessions, where `x11` is `None`
+pub fn read_clipboard_formats(
+  formats: &[SelectionFormat],
+  x11: Option<&X11Connection>,
+) -> SelectionFormats {
+  read_formats(Selection::Clipboard, formats, x11)
+}
+
+pub fn read_primary_formats(
+  formats: &[SelectionFormat],
+  x11: Option<&X11Connection>,
+) -> SelectionFormats {
+  read_formats(Selection::Primary, formats, x11)
+}
+
+fn read_formats(
+  selection: Selection,
+  formats: &[SelectionFormat],
+  x11: Option<&X11Connection>,
+) -> SelectionFormats {
+  let offered = read_offered(selection, x11).unwrap_or_default();
+  let read = |mime_type: &str| {
+    read_contents(selection, mime_type, x11)
+      .ok()
+      .flatten()
+      .map(|data| String::from_utf8_lossy(&data).into_owned())
+  };
+  let html = (formats.contains(&SelectionFormat::Html)
+    && offered.iter().any(|t| t == HTML_MIME_TYPE))
+  .then(|| read(HTML_MIME_TYPE))
+  .flatten();
+  let rtf = formats
+    .contains(&SelectionFormat::Rtf)
+    .then(|| {
+      RTF_MIME_TYPES
+        .iter()
+        .find(|mime_type| offered.iter().any(|t| t == *mime_type))
+        .and_then(|mime_type| read(mime_type))
+    })
+    .flatten();
+  SelectionFormats { offered, html, rtf }
+}
+
+fn read_offered(selection: Selection, x11: Option<&X11Connection>) -> Result<Vec<String>> {
+  match x11 {
+    Some(x11) => x11.read_targets(selection.atom_name()),
+    None if linux::is_wayland_session() => wayland::read_mime_types(selection.into()),
+    None => Ok(Vec::new()),
+  }
+}
+
+fn read_contents(
+  selection: Selection,
+  mime_type: &str,
+  x11: Option<&X11Connection>,
+) -> Result<Option<Vec<u8>>> {
+  match x11 {
+    Some(x11) => x11.read_selection(selection.atom_name(), mime_type),
+    None if linux::is_wayland_session() => wayland::read_contents(selection.into(), mime_type),
+    None => Ok(None),
+  }
+}
+
+impl Selection {
+  fn atom_name(self) -> &'static str {
+    match self {
+      Selection::Clipboard => "CLIPBOARD",
+      Selection::Primary => "PRIMARY",
+    }
+  }
+}
+
+impl From<Selection> for ClipboardType {
+  fn from(selection: Selection) -> Self {
+    match selection {
+      Selection::Clipboard => ClipboardType::Regular,
+      Selection::Primary => ClipboardType::Primary,
+    }
+  }
+}
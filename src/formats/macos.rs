@@ -0,0 +1,32 @@
+use objc2_app_kit::NSPasteboard;
+use objc2_foundation::NSString;
+
+use super::{SelectionFormat, SelectionFormats};
+
+pub fn read_clipboard_formats(formats: &[SelectionFormat]) -> SelectionFormats {
+  let pasteboard = NSPasteboard::generalPasteboard();
+  let read = |data_type: &str| {
+    pasteboard
+      .dataForType(&NSString::from_str(data_type))
+      .map(|data| String::from_utf8_lossy(&data.to_vec()).into_owned())
+  };
+  SelectionFormats {
+    offered: pasteboard
+      .types()
+      .map(|types| {
+        types
+          .iter()
+          .map(|data_type| data_type.to_string())
+          .collect()
+      })
+      .unwrap_or_default(),
+    html: formats
+      .contains(&SelectionFormat::Html)
+      .then(|| read("public.html"))
+      .flatten(),
+    rtf: formats
+      .contains(&SelectionFormat::Rtf)
+      .then(|| read("public.rtf"))
+      .flatten(),
+  }
+}
@@ -0,0 +1,37 @@
+//! Reads the formats the selection content is offered in, along with its rich text formats (HTML
+//! and RTF), right after it is copied to the clipboard (or from the primary selection in Linux).
+//!
+//! Reading rich formats is best effort: a format that cannot be read is left out instead of
+//! failing the whole selection retrieval.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(target_os = "linux")]
+pub use linux::{read_clipboard_formats, read_primary_formats};
+#[cfg(target_os = "macos")]
+pub use macos::read_clipboard_formats;
+#[cfg(windows)]
+pub use windows::read_clipboard_formats;
+
+/// Rich text format of the selection content
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SelectionFormat {
+  Html,
+  Rtf,
+}
+
+/// Formats the selection content is offered in
+#[derive(Debug, Default)]
+pub struct SelectionFormats {
+  /// Names of every format offered by the source, e.g. MIME types in Linux, UTIs in Mac and
+  /// clipboard format names in Windows
+  pub offered: Vec<String>,
+  pub html: Option<String>,
+  pub rtf: Option<String>,
+}
@@ -0,0 +1,44 @@
+use clipboard_win::{raw, Clipboard};
+
+use super::{SelectionFormat, SelectionFormats};
+
+/// Number of attempts to open the clipboard, which fails while another app has it open
+const OPEN_ATTEMPTS: usize = 10;
+
+pub fn read_clipboard_formats(formats: &[SelectionFormat]) -> SelectionFormats {
+  let Ok(_clipboard) = Clipboard::new_attempts(OPEN_ATTEMPTS) else {
+    return SelectionFormats::default();
+  };
+  SelectionFormats {
+    offered: raw::EnumFormats::new()
+      .filter_map(raw::format_name_big)
+      .collect(),
+    html: formats
+      .contains(&SelectionFormat::Html)
+      .then(read_html)
+      .flatten(),
+    rtf: formats
+      .contains(&SelectionFormat::Rtf)
+      .then(read_rtf)
+      .flatten(),
+  }
+}
+
+/// Reads the `HTML Format`, without the header that describes the fragment offsets
+fn read_html() -> Option<String> {
+  let format = raw::register_format("HTML Format")?;
+  let mut html = Vec::new();
+  raw::get_html(format.get(), &mut html).ok()?;
+  String::from_utf8(html).ok()
+}
+
+fn read_rtf() -> Option<String> {
+  let format = raw::register_format("Rich Text Format")?;
+  let mut rtf = Vec::new();
+  raw::get_vec(format.get(), &mut rtf).ok()?;
+  Some(
+    String::from_utf8_lossy(&rtf)
+      .trim_end_matches('\0')
+      .to_string(),
+  )
+}
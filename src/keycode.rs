@@ -0,0 +1,110 @@
+//! Physical keycodes of keys at their QWERTY position, which shortcuts are sent with instead of
+//! characters, so the shortcut does not depend on the active keyboard layout (e.g. `C` is not
+//! typeable in Cyrillic, Greek or Hebrew layouts, and is at a different position in Dvorak):
+//! * Windows - Scan codes, with `0x80` set for extended keys (as enigo expects)
+//! * Mac - Virtual keycodes (`kVK_*`)
+//! * Linux - X keycodes, i.e. evdev keycodes + 8
+//!
+//! `LETTERS`, `DIGITS` and `FUNCTION_KEYS` hold the keys from `A` to `Z`, `0` to `9` and `F1` to
+//! `F12` respectively.
+
+#[cfg(windows)]
+mod platform {
+  pub const LEFT_SHIFT: u16 = 0x2A;
+  pub const RIGHT_SHIFT: u16 = 0x36;
+  pub const LEFT_CONTROL: u16 = 0x1D;
+  pub const RIGHT_CONTROL: u16 = 0x9D;
+  pub const LEFT_ALT: u16 = 0x38;
+  pub const RIGHT_ALT: u16 = 0xB8;
+  pub const LEFT_META: u16 = 0xDB;
+  pub const RIGHT_META: u16 = 0xDC;
+  pub const LETTERS: [u16; 26] = [
+    0x1E, 0x30, 0x2E, 0x20, 0x12, 0x21, 0x22, 0x23, 0x17, 0x24, 0x25, 0x26, 0x32, 0x31, 0x18, 0x19,
+    0x10, 0x13, 0x1F, 0x14, 0x16, 0x2F, 0x11, 0x2D, 0x15, 0x2C,
+  ];
+  pub const DIGITS: [u16; 10] = [0x0B, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A];
+  pub const FUNCTION_KEYS: [u16; 12] = [
+    0x3B, 0x3C, 0x3D, 0x3E, 0x3F, 0x40, 0x41, 0x42, 0x43, 0x44, 0x57, 0x58,
+  ];
+  pub const INSERT: u16 = 0xD2;
+  pub const DELETE: u16 = 0xD3;
+  pub const ENTER: u16 = 0x1C;
+  pub const TAB: u16 = 0x0F;
+  pub const SPACE: u16 = 0x39;
+  pub const ESCAPE: u16 = 0x01;
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  pub const LEFT_SHIFT: u16 = 0x38;
+  pub const RIGHT_SHIFT: u16 = 0x3C;
+  pub const LEFT_CONTROL: u16 = 0x3B;
+  pub const RIGHT_CONTROL: u16 = 0x3E;
+  pub const LEFT_ALT: u16 = 0x3A;
+  pub const RIGHT_ALT: u16 = 0x3D;
+  pub const LEFT_META: u16 = 0x37;
+  pub const RIGHT_META: u16 = 0x36;
+  pub const LETTERS: [u16; 26] = [
+    0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F, 0x23,
+    0x0C, 0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+  ];
+  pub const DIGITS: [u16; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+  pub const FUNCTION_KEYS: [u16; 12] = [
+    0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F,
+  ];
+  /// `Help`, which is at the `Insert` position in Mac keyboards
+  pub const INSERT: u16 = 0x72;
+  pub const DELETE: u16 = 0x75;
+  pub const ENTER: u16 = 0x24;
+  pub const TAB: u16 = 0x30;
+  pub const SPACE: u16 = 0x31;
+  pub const ESCAPE: u16 = 0x35;
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  pub const LEFT_SHIFT: u16 = 50;
+  pub const RIGHT_SHIFT: u16 = 62;
+  pub const LEFT_CONTROL: u16 = 37;
+  pub const RIGHT_CONTROL: u16 = 105;
+  pub const LEFT_ALT: u16 = 64;
+  pub const RIGHT_ALT: u16 = 108;
+  pub const LEFT_META: u16 = 133;
+  pub const RIGHT_META: u16 = 134;
+  pub const LETTERS: [u16; 26] = [
+    38, 56, 54, 40, 26, 41, 42, 43, 31, 44, 45, 46, 58, 57, 32, 33, 24, 27, 39, 28, 30, 55, 25, 53,
+    29, 52,
+  ];
+  pub const DIGITS: [u16; 10] = [19, 10, 11, 12, 13, 14, 15, 16, 17, 18];
+  pub const FUNCTION_KEYS: [u16; 12] = [67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 95, 96];
+  pub const INSERT: u16 = 118;
+  pub const DELETE: u16 = 119;
+  pub const ENTER: u16 = 36;
+  pub const TAB: u16 = 23;
+  pub const SPACE: u16 = 65;
+  pub const ESCAPE: u16 = 9;
+}
+
+pub use platform::*;
+
+pub const C: u16 = LETTERS[2];
+pub const V: u16 = LETTERS[21];
+
+/// Modifier key of the copy shortcut, i.e. `Ctrl` (`Cmd` in Mac)
+pub const COPY_MODIFIER: u16 = if cfg!(target_os = "macos") {
+  LEFT_META
+} else {
+  LEFT_CONTROL
+};
+
+/// Every modifier key, which changes the meaning of the copy shortcut if held down along with it
+pub const MODIFIERS: [u16; 8] = [
+  LEFT_SHIFT,
+  RIGHT_SHIFT,
+  LEFT_CONTROL,
+  RIGHT_CONTROL,
+  LEFT_ALT,
+  RIGHT_ALT,
+  LEFT_META,
+  RIGHT_META,
+];
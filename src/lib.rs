@@ -3,60 +3,250 @@
 #[macro_use]
 extern crate napi_derive;
 
-use arboard::Clipboard;
-use enigo::{
-  Direction::{Click, Press, Release},
-  Enigo, Key, Keyboard, Settings,
-};
-use std::{thread, time};
+mod apps;
+mod backend;
+mod error;
+mod formats;
+mod keycode;
+#[cfg(target_os = "linux")]
+mod linux;
+mod lock;
+mod options;
+mod reader;
+mod replace;
+mod result;
+mod selection;
+mod shortcut;
+mod snapshot;
+mod source;
+mod task;
+mod typing;
+mod watch;
+mod zeroize;
 
-static DEFAULT_COPY_WAIT_TIME_MS: u32 = 5;
+use error::Result;
+use napi::{bindgen_prelude::AsyncTask, Either, JsFunction};
+use options::{
+  GetSelectionTextOptions, ReplaceSelectionTextOptions, TypeTextOptions, WatchSelectionOptions,
+};
+use result::SelectionResult;
+use std::time::Instant;
+use task::{GetSelectionTextTask, TypeTextTask};
+use watch::SelectionWatcher;
 
 /// Returns the current selection text. If there is no selection text, returns an empty string.
-/// 
+///
 /// The selection text is retrieved in a 3 steps processes:
-/// 1. Save clipboard existing text and clear clipboard
+/// 1. Save clipboard existing content (in every format, e.g. text, HTML, image) and clear clipboard
 /// 2. Simulate `Ctrl + C` (`Cmd + C` in Mac) keyboard input to copy selection text to clipboard
-/// 3. Read clipboard to retrieve selection text and return it as result 
-/// (the previous clipboard text is restored before returning to minimize side effects to users) 
-/// 
+/// 3. Poll clipboard until the copied selection text lands on it and return it as result
+/// (the previous clipboard content is restored before returning to minimize side effects to users,
+/// and the clipboard is cleared if it was empty)
+///
+/// In Linux, the selection text is read from the primary selection by default instead (X11 or
+/// Wayland compositors supporting the data-control protocol), which holds the highlighted text
+/// already, so no keyboard input is simulated and the clipboard is not touched.
+///
+/// ##### Arguments
+/// * `options` - An optional object to customize how the selection text is retrieved, see
+///               `GetSelectionTextOptions`. For backward compatibility, a number can be passed
+///               instead, which sets `copyWaitTimeMs`.
+///
+/// ##### Errors
+/// Throws an `Error` whose `code` property is one of:
+/// * `CLIPBOARD_UNAVAILABLE` - The clipboard could not be opened or cleared
+/// * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created, e.g. missing
+///                            accessibility permission in Mac
+/// * `KEY_SIMULATION_FAILED` - Simulating the copy keyboard input failed
+/// * `CLIPBOARD_RESTORE_FAILED` - The clipboard previous content could not be restored
+/// * `STRATEGY_UNSUPPORTED` - The selection strategy is not available on the current platform
+/// * `INVALID_SHORTCUT` - A `copyShortcut` description could not be parsed, e.g. unknown key name
+/// * `FOCUS_CHANGED` - The input focus moved to another window during the copy, see
+///                     `focusChangePolicy`
+/// * `CLIPBOARD_CONCEALED` - The clipboard holds concealed content, e.g. a password, see
+///                           `skipIfConcealed`
+/// * `APP_DENIED` - The focused app is denied, or not allowed, to retrieve the selection from, see
+///                  `denyApps` and `allowApps`
+/// * `INVALID_APP_MATCHER` - A `denyApps` or `allowApps` app could not be parsed, e.g. its `title`
+///                           is not a valid regular expression
+/// * `BUSY` - Another call is using the clipboard, see `failIfBusy`
+#[napi]
+pub fn get_selection_text(options: Option<Either<u32, GetSelectionTextOptions>>) -> Result<String> {
+  selection::get_selection_text(&options::resolve_options(options)?)
+}
+
+/// Async version of `getSelectionText`. It performs the same process on a worker thread instead of
+/// the JS thread, so the event loop is not blocked while waiting for the copy to complete.
+///
+/// Returns a promise that resolves with the selection text, or rejects with the same errors that
+/// `getSelectionText` throws.
+///
+/// ##### Arguments
+/// * `options` - Same as `getSelectionText`
+#[napi(ts_return_type = "Promise<string>")]
+pub fn get_selection_text_async(
+  options: Option<Either<u32, GetSelectionTextOptions>>,
+) -> AsyncTask<GetSelectionTextTask> {
+  AsyncTask::new(GetSelectionTextTask {
+    options: options::resolve_options(options),
+  })
+}
+
+/// Returns the current selection along with its rich text formats and metadata. It performs the
+/// same single copy (or primary selection read in Linux) as `getSelectionText`, reading every
+/// format while the selection is on the clipboard, so the clipboard is only touched once.
+///
+/// The returned object holds the selection `text`, its `html` and `rtf` if the source offered them,
+/// every format the source offered it in (`offeredFormats`), the `strategy` that retrieved it and
+/// the time it took (`elapsedMs`).
+///
+/// ##### Arguments
+/// * `options` - Same as `getSelectionText`. The `formats` option sets which rich text formats to
+///               read.
+///
+/// ##### Errors
+/// Throws the same errors as `getSelectionText`. A rich text format that cannot be read is left
+/// out instead.
+#[napi]
+pub fn get_selection(
+  options: Option<Either<u32, GetSelectionTextOptions>>,
+) -> Result<SelectionResult> {
+  let start = Instant::now();
+  let options = options::resolve_options(options)?;
+  let (selection, strategy) = selection::read_selection(&options)?;
+  Ok(SelectionResult::new(
+    selection,
+    strategy,
+    start.elapsed().as_secs_f64() * 1000.0,
+  ))
+}
+
+/// Replaces the current selection with the text, e.g. the selection transformed (translated,
+/// proofread) after being retrieved with `getSelectionText`. The text is pasted in a 3 steps
+/// process:
+/// 1. Save clipboard existing content (in every format) and set the clipboard to the text
+/// 2. Simulate `Ctrl + V` (`Cmd + V` in Mac) keyboard input to paste the text over the selection
+/// 3. Wait for the focused app to read the clipboard and restore the clipboard previous content
+///
+/// The modifier keys the user is holding down and the keyboard layout are handled as when copying
+/// the selection. In Linux X11, terminal emulators are pasted into with `Ctrl + Shift + V`.
+///
 /// ##### Arguments
-/// * `copyWaitTimeMs` - An optional number that sets how long to wait after performing the copy
-///                      operation before reading the clipboard text. It defaults to 5ms, which 
-///                      works for most use cases with small selection text. However, a larger value 
-///                      would be needed to support use case for large selection text that takes 
-///                      longer to copy to the clipboard. 
+/// * `text` - The text to replace the selection with
+/// * `options` - An optional object to customize how the text is pasted, see
+///               `ReplaceSelectionTextOptions`
+///
+/// ##### Errors
+/// Throws an `Error` whose `code` property is one of:
+/// * `CLIPBOARD_UNAVAILABLE` - The clipboard could not be opened or written
+/// * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created
+/// * `KEY_SIMULATION_FAILED` - Simulating the paste keyboard input failed
+/// * `CLIPBOARD_RESTORE_FAILED` - The clipboard previous content could not be restored
+/// * `INVALID_SHORTCUT` - The `pasteShortcut` description could not be parsed
+/// * `CLIPBOARD_CONCEALED` - The clipboard holds concealed content, see `skipIfConcealed`
+/// * `BUSY` - Another call is using the clipboard, see `failIfBusy`
 #[napi]
-pub fn get_selection_text(copy_wait_time_ms: Option<u32>) -> String {
-  let mut clipboard = Clipboard::new().unwrap();
-
-  // Save clipboard existing text
-  let clipboard_existing_text = clipboard.get_text().unwrap_or(String::new());
-
-  // Clear clipboard
-  clipboard.clear().unwrap();
-
-  // Simulate Ctrl/Cmd + C keyboard input to copy selection text to clipboard
-  let mut enigo = Enigo::new(&Settings::default()).unwrap();
-  let control_or_command_key = if cfg!(target_os = "macos") {
-    Key::Meta
-  } else {
-    Key::Control
-  };
-  enigo.key(control_or_command_key, Press).unwrap();
-  enigo.key(Key::Unicode('c'), Click).unwrap();
-  enigo.key(control_or_command_key, Release).unwrap();
-  
-  // Wait for clipboard to be updated with copied selection text
-  thread::sleep(time::Duration::from_millis(u64::from(copy_wait_time_ms.unwrap_or(DEFAULT_COPY_WAIT_TIME_MS))));
-
-  // Read clipboard to retrieve selection text
-  let selection_text = clipboard.get_text().unwrap_or(String::new());
-
-  // Restore clipboard previous existing text to minimize side effects to users
-  if !clipboard_existing_text.is_empty() {
-    clipboard.set_text(&clipboard_existing_text).unwrap();
-  }
-
-  return selection_text;
+pub fn replace_selection_text(
+  text: String,
+  options: Option<ReplaceSelectionTextOptions>,
+) -> Result<()> {
+  replace::replace_selection_text(&text, &options.unwrap_or_default().try_into()?)
+}
+
+/// Types the text at the cursor of the focused app by simulating keyboard input, without touching
+/// the clipboard, e.g. to insert short text. It is performed on a worker thread, so the event loop
+/// is not blocked while typing.
+///
+/// The text is typed in chunks of `chunkSize` characters, including characters the keyboard layout
+/// cannot produce (e.g. `é` with a US layout), which are typed through Unicode keyboard events in
+/// Windows and Mac, and by remapping a spare key in Linux. The modifier keys the user is holding
+/// down are handled as when copying the selection.
+///
+/// Returns a promise that resolves once the whole text is typed.
+///
+/// ##### Arguments
+/// * `text` - The text to type
+/// * `options` - An optional object to customize how the text is typed, see `TypeTextOptions`
+///
+/// ##### Errors
+/// Rejects with an `Error` whose `code` property is one of:
+/// * `KEYBOARD_INIT_FAILED` - The keyboard input simulator could not be created
+/// * `KEY_SIMULATION_FAILED` - Simulating the keyboard input failed. The failed chunk is not typed
+///                             again, as some of its characters may have been typed already
+/// * `CANCELLED` - `cancelTyping` was called before the whole text was typed
+#[napi(ts_return_type = "Promise<void>")]
+pub fn type_text(text: String, options: Option<TypeTextOptions>) -> AsyncTask<TypeTextTask> {
+  AsyncTask::new(TypeTextTask {
+    text,
+    options: options.unwrap_or_default().into(),
+    ticket: typing::TypingTicket::new(),
+  })
+}
+
+/// Cancels the `typeText` calls in progress, which stop before their next chunk and reject with a
+/// `CANCELLED` error. The `typeText` calls made afterward are not affected.
+#[napi]
+pub fn cancel_typing() {
+  typing::cancel_typing();
+}
+
+/// Watches the selection, calling the callback with the selection text each time it changes, e.g.
+/// the user highlights other text. It relies on the X server notifying the selection owner
+/// changes (XFIXES extension), so no keyboard input is simulated and the clipboard is not touched.
+///
+/// The selection text is reported once it has not changed for `debounceMs`, so a selection being
+/// extended is reported once. Empty text and text equal to the last reported one are skipped.
+///
+/// Returns a `SelectionWatcher`, whose `stop()` stops watching. The process is kept alive until
+/// then.
+///
+/// ##### Arguments
+/// * `callback` - Called with `(null, text)` for each selection change, or with an `Error` (with
+///                the same `code` property as the errors `getSelectionText` throws) if the
+///                selection cannot be read. Watching stops if the X server connection is lost.
+/// * `options` - An optional object to customize how the changes are reported, see
+///               `WatchSelectionOptions`
+///
+/// ##### Errors
+/// Throws an `Error` whose `code` property is one of:
+/// * `STRATEGY_UNSUPPORTED` - Not in a Linux X11 session, or the X server does not support the
+///                            XFIXES extension
+/// * `CLIPBOARD_UNAVAILABLE` - The X server could not be connected to
+#[napi(
+  ts_args_type = "callback: (err: Error | null, text: string) => void, options?: WatchSelectionOptions"
+)]
+pub fn watch_selection(
+  callback: JsFunction,
+  options: Option<WatchSelectionOptions>,
+) -> Result<SelectionWatcher> {
+  watch::watch_selection(callback, options.unwrap_or_default())
+}
+
+/// Listens to the clipboard changes, calling the callback with the new clipboard content each time
+/// the user (or an app) sets it, e.g. the user copies something. It relies on the X server
+/// notifying the clipboard owner changes (XFIXES extension) in X11, or on the data-control protocol
+/// in Wayland, so the clipboard is not polled.
+///
+/// The transient clipboard changes `getSelectionText`, `getSelection` and `replaceSelectionText`
+/// cause while copying (or pasting) the selection (i.e. clearing the clipboard, copying the
+/// selection and restoring the clipboard) are not reported, as the clipboard changes to one of the
+/// last contents this process set are left out.
+///
+/// Returns a `SelectionWatcher`, whose `stop()` stops listening. The process is kept alive until
+/// then.
+///
+/// ##### Arguments
+/// * `callback` - Called with `(null, change)` for each clipboard change, where `change` holds the
+///                clipboard `text` (`null` if it has no text) and every format it is offered in
+///                (`formats`), or with an `Error` if the listening fails, which stops it
+///
+/// ##### Errors
+/// Throws an `Error` whose `code` property is one of:
+/// * `STRATEGY_UNSUPPORTED` - Not in a Linux X11 or Wayland session, the X server does not support
+///                            the XFIXES extension, or the Wayland compositor does not support the
+///                            data-control protocol
+/// * `CLIPBOARD_UNAVAILABLE` - The X server or Wayland compositor could not be connected to
+#[napi(ts_args_type = "callback: (err: Error | null, change: ClipboardChange) => void")]
+pub fn on_clipboard_change(callback: JsFunction) -> Result<SelectionWatcher> {
+  watch::on_clipboard_change(callback)
 }
@@ -0,0 +1,187 @@
+pub mod terminal;
+pub mod wayland;
+pub mod x11;
+
+use std::{env, fs, sync::mpsc::Receiver, time::Duration};
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::snapshot::PASSWORD_MANAGER_HINT_MIME_TYPE;
+use crate::source::SelectionSource;
+use crate::watch;
+
+/// X11 targets (and MIME types) the pasted text is offered in
+const TEXT_TARGETS: [&str; 5] = [
+  "UTF8_STRING",
+  "text/plain;charset=utf-8",
+  "STRING",
+  "TEXT",
+  "text/plain",
+];
+
+/// Returns whether the current session is a Wayland session
+pub fn is_wayland_session() -> bool {
+  env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty())
+}
+
+/// Returns whether the current session is an X11 session
+pub fn is_x11_session() -> bool {
+  match env::var("XDG_SESSION_TYPE") {
+    Ok(session_type) if !session_type.is_empty() => session_type == "x11",
+    _ => env::var_os("DISPLAY").is_some() && !is_wayland_session(),
+  }
+}
+
+/// Returns whether the highlighted text can be read from the primary selection directly, i.e. it
+/// is an X11 session or a Wayland session whose compositor supports the data-control protocol
+pub fn is_primary_selection_supported() -> bool {
+  if is_wayland_session() {
+    wayland::is_primary_selection_supported()
+  } else {
+    is_x11_session()
+  }
+}
+
+/// Connects to the X server in X11 sessions, or returns `None` in other sessions
+pub fn connect_x11() -> Result<Option<x11::X11Connection>> {
+  is_x11_session().then(x11::X11Connection::new).transpose()
+}
+
+/// Read of the text this process set the clipboard content to, by the app it is pasted into
+pub enum PendingRead {
+  X11(x11::SelectionRead),
+  /// Signalled once the single paste the text is served for is done
+  Wayland(Receiver<()>),
+}
+
+impl PendingRead {
+  /// Waits until the text is read or the timeout elapses
+  pub fn wait(&self, timeout: Duration) {
+    match self {
+      Self::X11(read) => {
+        read.wait(timeout);
+      }
+      Self::Wayland(served) => {
+        let _ = served.recv_timeout(timeout);
+      }
+    }
+  }
+}
+
+/// Sets the clipboard content to the text to paste in a way its read can be told: in X11, the
+/// text is served by this process, which sees the requests of the focused app. In Wayland, where
+/// the requestor is unknown, the text is served to a single paste, which is only done with
+/// `until_read` (the clipboard is restored right after) and `exclude_from_history` (clipboard
+/// managers honoring the marker do not read the text first), and while no `onClipboardChange`
+/// listener of this process would read it. Returns `None` if the text is not set, so it has to be
+/// set through arboard
+pub fn write_pasted_text(
+  text: &str,
+  exclude_from_history: bool,
+  until_read: bool,
+) -> Result<Option<PendingRead>> {
+  if is_x11_session() {
+    let mut targets: Vec<_> = TEXT_TARGETS
+      .into_iter()
+      .map(|target| x11::SelectionTarget::new(target, text.as_bytes().to_vec()))
+      .collect();
+    if exclude_from_history {
+      targets.push(x11::SelectionTarget::new(
+        PASSWORD_MANAGER_HINT_MIME_TYPE,
+        b"secret".to_vec(),
+      ));
+    }
+    return Ok(Some(PendingRead::X11(x11::serve_selection(
+      "CLIPBOARD",
+      targets,
+    )?)));
+  }
+  if is_wayland_session() && until_read && exclude_from_history && !watch::has_clipboard_listeners()
+  {
+    // Compositors without the data-control protocol are left to arboard
+    if let Ok(served) = wayland::copy_text_once(text) {
+      return Ok(Some(PendingRead::Wayland(served)));
+    }
+  }
+  Ok(None)
+}
+
+/// Returns the application whose window has the input focus, from its EWMH properties (read
+/// through the connection to the X server) and its process
+pub fn active_window_source(x11: &x11::X11Connection) -> Option<SelectionSource> {
+  let window = x11.active_window()?;
+  let mut class = window.class.into_iter();
+  Some(SelectionSource {
+    title: window.title,
+    instance: class.next(),
+    class: class.next(),
+    pid: window.pid,
+    exe_path: window.pid.and_then(exe_path),
+  })
+}
+
+/// Returns the name of the process, which the kernel truncates to 15 bytes, or `None` if it cannot
+/// be read, e.g. it has exited
+pub fn process_name(pid: u32) -> Option<String> {
+  let name = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+  Some(name.trim_end().to_string())
+}
+
+/// Returns the path of the executable of the process, or `None` if it cannot be read, e.g. it has
+/// exited or belongs to another user
+pub fn exe_path(pid: u32) -> Option<String> {
+  let path = fs::read_link(format!("/proc/{pid}/exe")).ok()?;
+  Some(path.to_string_lossy().into_owned())
+}
+
+/// Listener of the clipboard changes, i.e. the user (or an app) sets the clipboard content,
+/// through the XFIXES extension in X11 or the data-control protocol in Wayland
+pub enum ClipboardListener {
+  X11(Box<x11::SelectionOwnerListener>),
+  Wayland(wayland::SelectionChangeListener),
+}
+
+impl ClipboardListener {
+  /// ##### Errors
+  /// * `STRATEGY_UNSUPPORTED` - Not an X11 session nor a data-control capable Wayland session
+  /// * `CLIPBOARD_UNAVAILABLE` - The X server or Wayland compositor could not be connected to
+  pub fn new() -> Result<Self> {
+    if !is_wayland_session() && !is_x11_session() {
+      return Err(Error::new(
+        ErrorCode::StrategyUnsupported,
+        "The clipboard changes are only reported in X11 and Wayland sessions".to_string(),
+      ));
+    }
+    if is_wayland_session() {
+      Ok(ClipboardListener::Wayland(
+        wayland::SelectionChangeListener::new(wayland::ClipboardType::Regular)?,
+      ))
+    } else {
+      Ok(ClipboardListener::X11(Box::new(
+        x11::SelectionOwnerListener::new("CLIPBOARD")?,
+      )))
+    }
+  }
+
+  /// Returns whether the clipboard changed since the last call, without blocking
+  pub fn poll(&self) -> Result<bool> {
+    match self {
+      ClipboardListener::X11(listener) => listener.poll(),
+      ClipboardListener::Wayland(listener) => listener.poll(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::process;
+
+  use super::*;
+
+  #[test]
+  fn reads_process_of_pid() {
+    let exe = env::current_exe().unwrap();
+    assert_eq!(exe_path(process::id()).as_deref(), exe.to_str(),);
+    let file_name = exe.file_name().unwrap().to_string_lossy();
+    assert!(file_name.starts_with(&process_name(process::id()).unwrap()));
+  }
+}
@@ -0,0 +1,135 @@
+use crate::source::SelectionSource;
+
+/// Terminal emulators, by the names their windows (`WM_CLASS` instance or class) or processes go
+/// by, where `Ctrl + C` interrupts the running program instead of copying the selection
+pub const DEFAULT_TERMINALS: &[&str] = &[
+  "alacritty",
+  "aterm",
+  "blackbox",
+  "com.gexperts.tilix",
+  "com.mitchellh.ghostty",
+  "contour",
+  "cool-retro-term",
+  "deepin-terminal",
+  "eterm",
+  "foot",
+  "footclient",
+  "ghostty",
+  "gnome-terminal",
+  "gnome-terminal-server",
+  "guake",
+  "hyper",
+  "kgx",
+  "kitty",
+  "konsole",
+  "lxterminal",
+  "mate-terminal",
+  "org.gnome.console",
+  "org.gnome.ptyxis",
+  "org.wezfurlong.wezterm",
+  "ptyxis",
+  "qterminal",
+  "rio",
+  "rxvt",
+  "sakura",
+  "st",
+  "st-256color",
+  "tabby",
+  "terminator",
+  "terminology",
+  "tilda",
+  "tilix",
+  "urxvt",
+  "uxterm",
+  "wezterm",
+  "wezterm-gui",
+  "xfce4-terminal",
+  "xterm",
+  "yakuake",
+  "zutty",
+];
+
+/// Longest process name the kernel keeps (`/proc/<pid>/comm`), longer ones are truncated
+const MAX_PROCESS_NAME_LENGTH: usize = 15;
+
+/// Returns whether the window is a terminal emulator, i.e. its `WM_CLASS` instance or class, or
+/// the name of its process, is one of [`DEFAULT_TERMINALS`] or `terminals` (case insensitive)
+pub fn is_terminal(source: &SelectionSource, terminals: &[String]) -> bool {
+  let process_name = source.pid.and_then(super::process_name);
+  is_terminal_named(source, process_name.as_deref(), terminals)
+}
+
+fn is_terminal_named(
+  source: &SelectionSource,
+  process_name: Option<&str>,
+  terminals: &[String],
+) -> bool {
+  let mut names = DEFAULT_TERMINALS
+    .iter()
+    .copied()
+    .chain(terminals.iter().map(String::as_str));
+  names.any(|name| {
+    [&source.instance, &source.class]
+      .into_iter()
+      .flatten()
+      .any(|class| class.eq_ignore_ascii_case(name))
+      || process_name.is_some_and(|process_name| {
+        let name = name.get(..MAX_PROCESS_NAME_LENGTH).unwrap_or(name);
+        process_name.eq_ignore_ascii_case(name)
+      })
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn window(class: &[&str]) -> SelectionSource {
+    SelectionSource {
+      instance: class.first().map(|name| name.to_string()),
+      class: class.get(1).map(|name| name.to_string()),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn detects_terminal_by_window_class() {
+    assert!(is_terminal_named(&window(&["xterm", "XTerm"]), None, &[]));
+    assert!(is_terminal_named(
+      &window(&["gnome-terminal-server", "Gnome-terminal"]),
+      None,
+      &[]
+    ));
+    assert!(!is_terminal_named(
+      &window(&["Navigator", "firefox"]),
+      None,
+      &[]
+    ));
+  }
+
+  #[test]
+  fn detects_terminal_by_truncated_process_name() {
+    // `/proc/<pid>/comm` of `gnome-terminal-server`
+    assert!(is_terminal_named(
+      &window(&[]),
+      Some("gnome-terminal-"),
+      &[]
+    ));
+    assert!(!is_terminal_named(&window(&[]), Some("firefox"), &[]));
+  }
+
+  #[test]
+  fn detects_extra_terminals() {
+    let terminals = vec!["MyTerm".to_string()];
+    assert!(is_terminal_named(
+      &window(&["myterm", "MyTerm"]),
+      None,
+      &terminals
+    ));
+    assert!(!is_terminal_named(
+      &window(&["myterm", "MyTerm"]),
+      None,
+      &[]
+    ));
+  }
+}
@@ -0,0 +1,162 @@
+use std::{
+  io::Read,
+  sync::mpsc::{self, Receiver, TryRecvError},
+  thread,
+};
+pub use wl_clipboard_rs::paste::ClipboardType;
+use wl_clipboard_rs::{
+  copy::{self, ServeRequests, Source},
+  paste::{self, get_contents, Error as PasteError, MimeType, Seat},
+  utils,
+  watch::{self, CancelHandle, Watcher},
+};
+
+use crate::error::{ErrorCode, Result, WithErrorCode};
+
+/// Returns whether the compositor lets the primary selection be read without keyboard focus, i.e.
+/// it offers `zwlr_data_control_manager_v1` version 2 or above (or `ext_data_control_manager_v1`)
+pub fn is_primary_selection_supported() -> bool {
+  utils::is_primary_selection_supported().unwrap_or(false)
+}
+
+/// Returns the MIME types the selection owner offers, in its order of preference
+pub fn read_mime_types(clipboard: ClipboardType) -> Result<Vec<String>> {
+  match paste::get_mime_types_ordered(clipboard, Seat::Unspecified) {
+    Ok(mime_types) => Ok(mime_types),
+    Err(PasteError::ClipboardEmpty | PasteError::NoMimeType) => Ok(Vec::new()),
+    Err(err) => Err(err).with_code(ErrorCode::ClipboardUnavailable),
+  }
+}
+
+/// Reads the selection content of the MIME type. Returns `None` if it is not offered
+pub fn read_contents(clipboard: ClipboardType, mime_type: &str) -> Result<Option<Vec<u8>>> {
+  match get_contents(clipboard, Seat::Unspecified, MimeType::Specific(mime_type)) {
+    Ok((mut pipe, _)) => {
+      let mut bytes = Vec::new();
+      pipe
+        .read_to_end(&mut bytes)
+        .with_code(ErrorCode::ClipboardUnavailable)?;
+      Ok(Some(bytes))
+    }
+    Err(PasteError::ClipboardEmpty | PasteError::NoMimeType) => Ok(None),
+    Err(err) => Err(err).with_code(ErrorCode::ClipboardUnavailable),
+  }
+}
+
+/// Reads the primary selection through the data-control protocol, which holds the currently
+/// highlighted text. Neither keyboard input is simulated nor the clipboard is touched
+pub fn get_primary_selection_text() -> Result<String> {
+  match get_contents(ClipboardType::Primary, Seat::Unspecified, MimeType::Text) {
+    Ok((mut pipe, _)) => {
+      let mut bytes = Vec::new();
+      pipe
+        .read_to_end(&mut bytes)
+        .with_code(ErrorCode::ClipboardUnavailable)?;
+      Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+    Err(PasteError::ClipboardEmpty | PasteError::NoMimeType) => Ok(String::new()),
+    Err(err @ (PasteError::PrimarySelectionUnsupported | PasteError::MissingProtocol { .. })) => {
+      Err(err).with_code(ErrorCode::StrategyUnsupported)
+    }
+    Err(err) => Err(err).with_code(ErrorCode::ClipboardUnavailable),
+  }
+}
+
+/// Sets the clipboard content to the text, marked for clipboard managers to not record it, and
+/// serves it to a single paste only. Returns a receiver signalled once it is pasted, or the
+/// clipboard is set by another app. Requests of the history exclusion marker do not count as the
+/// paste
+pub fn copy_text_once(text: &str) -> Result<Receiver<()>> {
+  let mut options = copy::Options::new();
+  options
+    .serve_requests(ServeRequests::Only(1))
+    .sensitive(true);
+  let source = Source::Bytes(text.as_bytes().into());
+  let (prepared_sender, prepared) = mpsc::sync_channel(1);
+  let (served_sender, served) = mpsc::channel();
+  // The copy is prepared on the serving thread, as it cannot be sent across threads
+  thread::Builder::new()
+    .name("wayland-paste-source".to_string())
+    .spawn(
+      move || match options.prepare_copy(source, copy::MimeType::Text) {
+        Ok(prepared_copy) => {
+          let _ = prepared_sender.send(Ok(()));
+          let _ = prepared_copy.serve();
+          let _ = served_sender.send(());
+        }
+        Err(err) => {
+          let _ = prepared_sender.send(Err(err));
+        }
+      },
+    )
+    .with_code(ErrorCode::ClipboardUnavailable)?;
+  prepared
+    .recv()
+    .with_code(ErrorCode::ClipboardUnavailable)?
+    .with_code(ErrorCode::ClipboardUnavailable)?;
+  Ok(served)
+}
+
+/// Listener of the selection changes of a clipboard through the data-control protocol. The
+/// compositor events are awaited on a background thread, which stops when the listener is dropped
+pub struct SelectionChangeListener {
+  changes: Receiver<Result<()>>,
+  cancel: CancelHandle,
+}
+
+impl SelectionChangeListener {
+  /// ##### Errors
+  /// * `STRATEGY_UNSUPPORTED` - The compositor does not support the data-control protocol
+  /// * `CLIPBOARD_UNAVAILABLE` - The compositor could not be connected to
+  pub fn new(clipboard: ClipboardType) -> Result<Self> {
+    let mut watcher = match Watcher::new(watch::ClipboardType::from(clipboard), Seat::Unspecified) {
+      Ok(watcher) => watcher,
+      Err(err @ (PasteError::PrimarySelectionUnsupported | PasteError::MissingProtocol { .. })) => {
+        return Err(err).with_code(ErrorCode::StrategyUnsupported)
+      }
+      Err(err) => return Err(err).with_code(ErrorCode::ClipboardUnavailable),
+    };
+    let cancel = watcher.cancel_handle();
+    let (sender, changes) = mpsc::channel();
+    thread::spawn(move || {
+      // The first event reports the current selection rather than a change
+      let mut is_first_event = true;
+      loop {
+        let change = match watcher.next_event() {
+          Ok(Some(_)) if is_first_event => {
+            is_first_event = false;
+            continue;
+          }
+          Ok(Some(_)) => Ok(()),
+          Ok(None) => return,
+          Err(err) => Err(err).with_code(ErrorCode::ClipboardUnavailable),
+        };
+        let failed = change.is_err();
+        if sender.send(change).is_err() || failed {
+          return;
+        }
+      }
+    });
+    Ok(SelectionChangeListener { changes, cancel })
+  }
+
+  /// Returns whether the selection changed since the last call, without blocking
+  pub fn poll(&self) -> Result<bool> {
+    let mut changed = false;
+    loop {
+      match self.changes.try_recv() {
+        Ok(change) => {
+          change?;
+          changed = true;
+        }
+        Err(TryRecvError::Empty | TryRecvError::Disconnected) => return Ok(changed),
+      }
+    }
+  }
+}
+
+impl Drop for SelectionChangeListener {
+  fn drop(&mut self) {
+    self.cancel.cancel();
+  }
+}
@@ -0,0 +1,1152 @@
+use arboard::{Clipboard, GetExtLinux, LinuxClipboardKind};
+#[cfg(test)]
+use std::cell::Cell;
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Condvar, Mutex, MutexGuard, Once,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+use x11rb::{
+  connection::{Connection, RequestConnection},
+  errors::ConnectError,
+  protocol::{
+    xfixes::{ConnectionExt as _, SelectionEventMask},
+    xinput::{ConnectionExt as _, DeviceUse, InputStateData},
+    xproto::{
+      Atom, AtomEnum, ConnectionExt, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+      SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+    },
+    xtest::ConnectionExt as _,
+    Event,
+  },
+  rust_connection::RustConnection,
+  wrapper::ConnectionExt as _,
+  COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+use crate::error::{ErrorCode, Result, WithErrorCode};
+use crate::zeroize;
+
+/// Reads the PRIMARY selection through the clipboard, which holds the currently highlighted text.
+/// Neither keyboard input is simulated nor the clipboard (i.e. CLIPBOARD selection) is touched
+pub fn get_primary_selection_text(clipboard: &mut Clipboard) -> Result<String> {
+  match clipboard
+    .get()
+    .clipboard(LinuxClipboardKind::Primary)
+    .text()
+  {
+    Ok(text) => Ok(text),
+    Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+    Err(err) => Err(err).with_code(ErrorCode::ClipboardUnavailable),
+  }
+}
+
+/// How long to wait for the selection owner to convert the selection
+const CONVERT_TIMEOUT: Duration = Duration::from_millis(500);
+
+type X11Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[cfg(test)]
+thread_local! {
+  /// Connections the thread opened to the X server, counted to check they are kept across reads
+  static CONNECTIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn connect() -> std::result::Result<(RustConnection, usize), ConnectError> {
+  #[cfg(test)]
+  CONNECTIONS.set(CONNECTIONS.get() + 1);
+  x11rb::connect(None)
+}
+
+/// Returns the number of connections the thread opened to the X server so far
+#[cfg(test)]
+pub fn connection_count() -> usize {
+  CONNECTIONS.get()
+}
+
+/// Targets that do not convert the selection content to a format, but list the formats or act on
+/// the selection, so they are not saved
+const META_TARGETS: [&str; 7] = [
+  "TARGETS",
+  "MULTIPLE",
+  "TIMESTAMP",
+  "SAVE_TARGETS",
+  "DELETE",
+  "INSERT_SELECTION",
+  "INSERT_PROPERTY",
+];
+
+/// Selection content converted to a target (i.e. format), as sent by the selection owner. The data
+/// is zeroized when dropped
+#[derive(Clone)]
+pub struct SelectionTarget {
+  /// Name of the target, e.g. `text/html`
+  pub name: String,
+  /// Type of the data, e.g. `UTF8_STRING`, or `NONE` for the target itself
+  type_: Atom,
+  /// Number of bits per item of the data: 8, 16 or 32
+  format: u8,
+  pub data: Vec<u8>,
+}
+
+impl SelectionTarget {
+  /// Content with the target as type, made of bytes
+  pub fn new(name: &str, data: Vec<u8>) -> Self {
+    SelectionTarget {
+      name: name.to_string(),
+      type_: NONE,
+      format: 8,
+      data,
+    }
+  }
+}
+
+impl Drop for SelectionTarget {
+  fn drop(&mut self) {
+    zeroize::vec(&mut self.data);
+  }
+}
+
+/// Result of the conversion of the selection to a target
+enum Conversion {
+  Data {
+    type_: Atom,
+    format: u8,
+    value: Vec<u8>,
+  },
+  /// The content is too large to be sent at once, and is sent incrementally (`INCR`) instead, which
+  /// is not supported
+  Incremental,
+  /// The selection has no owner, or the owner refuses the conversion or does not reply in time
+  Refused,
+}
+
+/// Connection to the X server kept to read the selections and the focused window, along with a
+/// window to receive the selection content on, so each read does not connect again
+pub struct X11Connection {
+  conn: RustConnection,
+  root: Window,
+  window: Window,
+}
+
+impl X11Connection {
+  /// ##### Errors
+  /// * `CLIPBOARD_UNAVAILABLE` - The X server could not be connected to
+  pub fn new() -> Result<Self> {
+    Self::connect().with_code(ErrorCode::ClipboardUnavailable)
+  }
+
+  fn connect() -> X11Result<Self> {
+    let (conn, screen_num) = connect()?;
+    let window = create_window(&conn, screen_num)?;
+    conn.flush()?;
+    Ok(X11Connection {
+      root: conn.setup().roots[screen_num].root,
+      conn,
+      window,
+    })
+  }
+
+  /// Returns whether the connection to the X server still works, with a round trip
+  pub fn is_connected(&self) -> bool {
+    self
+      .conn
+      .get_input_focus()
+      .is_ok_and(|cookie| cookie.reply().is_ok())
+  }
+
+  /// Reads the selection (e.g. `PRIMARY` or `CLIPBOARD`) content converted to the target (e.g.
+  /// `text/html`). Returns `None` if the selection has no owner, the owner refuses the conversion
+  /// or does not reply in time, or the content is too large to be sent at once (`INCR` transfers
+  /// are not supported)
+  pub fn read_selection(&self, selection: &str, target: &str) -> Result<Option<Vec<u8>>> {
+    self
+      .read_selection_with(selection, target)
+      .with_code(ErrorCode::ClipboardUnavailable)
+  }
+
+  fn read_selection_with(&self, selection: &str, target: &str) -> X11Result<Option<Vec<u8>>> {
+    let selection = intern_atom(&self.conn, selection)?;
+    let target = intern_atom(&self.conn, target)?;
+    match convert_selection(&self.conn, self.window, selection, target)? {
+      Conversion::Data { value, .. } => Ok(Some(value)),
+      Conversion::Incremental | Conversion::Refused => Ok(None),
+    }
+  }
+
+  /// Returns the names of the targets (i.e. formats) the selection owner offers
+  pub fn read_targets(&self, selection: &str) -> Result<Vec<String>> {
+    intern_atom(&self.conn, selection)
+      .and_then(|selection| read_target_atoms(&self.conn, self.window, selection))
+      .and_then(|atoms| atom_names(&self.conn, &atoms.unwrap_or_default()))
+      .with_code(ErrorCode::ClipboardUnavailable)
+  }
+
+  /// Reads the selection content converted to every target its owner offers, except the
+  /// [`META_TARGETS`], in the order the owner offers them. The targets the owner refuses to
+  /// convert to are left out. Returns `None` if the content cannot be read whole, i.e. the owner
+  /// refuses to list its targets or one of them is too large to be sent at once (`INCR` transfers
+  /// are not supported)
+  pub fn read_all_targets(&self, selection: &str) -> Result<Option<Vec<SelectionTarget>>> {
+    intern_atom(&self.conn, selection)
+      .and_then(|selection| read_all_targets_with(&self.conn, self.window, selection))
+      .with_code(ErrorCode::ClipboardUnavailable)
+  }
+
+  /// Returns the window that has the input focus (EWMH `_NET_ACTIVE_WINDOW`), or `None` if it
+  /// cannot be told, e.g. the window manager does not support EWMH
+  pub fn active_window(&self) -> Option<ActiveWindow> {
+    active_window_with(&self.conn, self.root).ok().flatten()
+  }
+}
+
+/// Returns the atoms of the targets the selection owner offers, or `None` if it refuses to list
+/// them
+fn read_target_atoms(
+  conn: &RustConnection,
+  window: Window,
+  selection: Atom,
+) -> X11Result<Option<Vec<Atom>>> {
+  let targets = intern_atom(conn, "TARGETS")?;
+  let Conversion::Data { value, .. } = convert_selection(conn, window, selection, targets)? else {
+    return Ok(None);
+  };
+  Ok(Some(
+    value
+      .chunks_exact(4)
+      .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+      .collect(),
+  ))
+}
+
+fn atom_names(conn: &RustConnection, atoms: &[Atom]) -> X11Result<Vec<String>> {
+  let cookies = atoms
+    .iter()
+    .map(|atom| conn.get_atom_name(*atom))
+    .collect::<std::result::Result<Vec<_>, _>>()?;
+  Ok(
+    cookies
+      .into_iter()
+      .filter_map(|cookie| cookie.reply().ok())
+      .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+      .collect(),
+  )
+}
+
+fn read_all_targets_with(
+  conn: &RustConnection,
+  window: Window,
+  selection: Atom,
+) -> X11Result<Option<Vec<SelectionTarget>>> {
+  if conn.get_selection_owner(selection)?.reply()?.owner == NONE {
+    return Ok(Some(Vec::new()));
+  }
+  let Some(atoms) = read_target_atoms(conn, window, selection)? else {
+    return Ok(None);
+  };
+  let mut targets: Vec<SelectionTarget> = Vec::with_capacity(atoms.len());
+  for atom in atoms {
+    let name = String::from_utf8_lossy(&conn.get_atom_name(atom)?.reply()?.name).into_owned();
+    if META_TARGETS.contains(&name.as_str()) || targets.iter().any(|target| target.name == name) {
+      continue;
+    }
+    match convert_selection(conn, window, selection, atom)? {
+      Conversion::Data {
+        type_,
+        format,
+        value,
+      } => targets.push(SelectionTarget {
+        name,
+        type_,
+        format,
+        data: value,
+      }),
+      Conversion::Incremental => return Ok(None),
+      Conversion::Refused => {}
+    }
+  }
+  Ok(Some(targets))
+}
+
+fn intern_atom(conn: &RustConnection, name: &str) -> X11Result<Atom> {
+  Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+}
+
+/// Creates an unmapped window to receive selection events on
+fn create_window(conn: &RustConnection, screen_num: usize) -> X11Result<Window> {
+  let root = conn.setup().roots[screen_num].root;
+  let window = conn.generate_id()?;
+  conn.create_window(
+    COPY_DEPTH_FROM_PARENT,
+    window,
+    root,
+    0,
+    0,
+    1,
+    1,
+    0,
+    WindowClass::INPUT_OUTPUT,
+    COPY_FROM_PARENT,
+    &CreateWindowAux::new(),
+  )?;
+  Ok(window)
+}
+
+/// Converts the selection to the target, with the content sent to a property of the window
+fn convert_selection(
+  conn: &RustConnection,
+  window: Window,
+  selection: Atom,
+  target: Atom,
+) -> X11Result<Conversion> {
+  let property = intern_atom(conn, "NODE_SELECTION")?;
+  let incr = intern_atom(conn, "INCR")?;
+  conn.convert_selection(window, selection, target, property, CURRENT_TIME)?;
+  conn.flush()?;
+
+  let deadline = Instant::now() + CONVERT_TIMEOUT;
+  loop {
+    match conn.poll_for_event()? {
+      // Notifications of earlier conversions that timed out are skipped
+      Some(Event::SelectionNotify(event))
+        if event.requestor == window && event.selection == selection && event.target == target =>
+      {
+        if event.property == NONE {
+          return Ok(Conversion::Refused);
+        }
+        let reply = conn
+          .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)?
+          .reply()?;
+        return Ok(match reply.type_ {
+          type_ if type_ == incr => Conversion::Incremental,
+          _ if !matches!(reply.format, 8 | 16 | 32) => Conversion::Refused,
+          type_ => Conversion::Data {
+            type_,
+            format: reply.format,
+            value: reply.value,
+          },
+        });
+      }
+      Some(_) => {}
+      None if Instant::now() >= deadline => return Ok(Conversion::Refused),
+      None => thread::sleep(Duration::from_millis(1)),
+    }
+  }
+}
+
+/// Content this process serves for a selection it owns
+struct ServedSelection {
+  selection: Atom,
+  targets: Vec<(Atom, SelectionTarget)>,
+  /// Resource id base of the client that owns the focused window, whose read of the content is
+  /// told, or `None` to tell the read of any client
+  reader: Option<u32>,
+  read: Arc<Signal>,
+}
+
+/// Flag the serving thread sets, which other threads wait for
+#[derive(Default)]
+struct Signal {
+  is_set: Mutex<bool>,
+  set: Condvar,
+}
+
+impl Signal {
+  fn set(&self) {
+    *lock(&self.is_set) = true;
+    self.set.notify_all();
+  }
+
+  fn reset(&self) {
+    *lock(&self.is_set) = false;
+  }
+
+  /// Waits until the flag is set or the timeout elapses, and returns whether it is set
+  fn wait(&self, timeout: Duration) -> bool {
+    let is_set = lock(&self.is_set);
+    let (is_set, _) = self
+      .set
+      .wait_timeout_while(is_set, timeout, |is_set| !*is_set)
+      .unwrap_or_else(|err| err.into_inner());
+    *is_set
+  }
+}
+
+/// Read of the content of a selection this process serves, by the app that had the input focus
+/// when it started serving it, or by any app if the focused window could not be told
+pub struct SelectionRead(Arc<Signal>);
+
+impl SelectionRead {
+  /// Waits until the content is read (in a target other than `TARGETS`) or the timeout elapses,
+  /// and returns whether it is read
+  pub fn wait(&self, timeout: Duration) -> bool {
+    self.0.wait(timeout)
+  }
+}
+
+/// How long to wait for the clipboard manager to save the clipboard content when this process
+/// exits
+const HANDOVER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Owner of the selections this process sets the content of, whose thread serves their content to
+/// the apps that request it. It is kept for the next selections served, until its connection to
+/// the X server is lost. The `CLIPBOARD` content is handed to the clipboard manager when the
+/// process exits
+struct SelectionServer {
+  conn: Arc<RustConnection>,
+  window: Window,
+  root: Window,
+  content: Arc<Mutex<Vec<ServedSelection>>>,
+  running: Arc<AtomicBool>,
+  /// Set once the clipboard manager saved the `CLIPBOARD` content
+  handed_over: Arc<Signal>,
+}
+
+static SELECTION_SERVER: Mutex<Option<SelectionServer>> = Mutex::new(None);
+
+/// Atoms the selection server handles the requests with
+struct ServerAtoms {
+  targets: Atom,
+  multiple: Atom,
+  save_targets: Atom,
+}
+
+impl ServerAtoms {
+  fn intern(conn: &RustConnection) -> X11Result<Self> {
+    Ok(ServerAtoms {
+      targets: intern_atom(conn, "TARGETS")?,
+      multiple: intern_atom(conn, "MULTIPLE")?,
+      save_targets: intern_atom(conn, "SAVE_TARGETS")?,
+    })
+  }
+}
+
+/// Hands the `CLIPBOARD` content this process serves to the clipboard manager when the process
+/// exits, as the content is gone along with its owner otherwise
+extern "C" fn hand_over_at_exit() {
+  let _ = hand_over_clipboard();
+}
+
+/// Asks the clipboard manager to save the `CLIPBOARD` content this process serves, if any. The
+/// server lock is not waited for, as the thread holding it may be stopped at exit
+fn hand_over_clipboard() -> X11Result<()> {
+  let Ok(server) = SELECTION_SERVER.try_lock() else {
+    return Ok(());
+  };
+  match server.as_ref() {
+    Some(server) if server.running.load(Ordering::SeqCst) => server.hand_over(),
+    _ => Ok(()),
+  }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Makes this process own the selection (e.g. `CLIPBOARD`) and serve the content converted to each
+/// of the targets, until another app sets the selection content. Unlike arboard, which can only
+/// serve text, HTML, image or file list, any target is served, as it was captured. Returns the
+/// read of the content by the focused app, which can be waited for
+pub fn serve_selection(selection: &str, targets: Vec<SelectionTarget>) -> Result<SelectionRead> {
+  with_selection_server(|server| server.serve(selection, targets))
+}
+
+/// Leaves the selection without owner, i.e. without content
+pub fn clear_selection(selection: &str) -> Result<()> {
+  with_selection_server(|server| server.clear(selection))
+}
+
+fn with_selection_server<T>(action: impl FnOnce(&SelectionServer) -> X11Result<T>) -> Result<T> {
+  let mut server = lock(&SELECTION_SERVER);
+  if !server
+    .as_ref()
+    .is_some_and(|server| server.running.load(Ordering::SeqCst))
+  {
+    *server = Some(SelectionServer::start().with_code(ErrorCode::ClipboardUnavailable)?);
+  }
+  action(
+    server
+      .as_ref()
+      .expect("the selection server was just started"),
+  )
+  .with_code(ErrorCode::ClipboardUnavailable)
+}
+
+impl SelectionServer {
+  fn start() -> X11Result<Self> {
+    let (conn, screen_num) = connect()?;
+    let window = create_window(&conn, screen_num)?;
+    conn.flush()?;
+    let server = SelectionServer {
+      root: conn.setup().roots[screen_num].root,
+      conn: Arc::new(conn),
+      window,
+      content: Arc::default(),
+      running: Arc::new(AtomicBool::new(true)),
+      handed_over: Arc::default(),
+    };
+    let conn = server.conn.clone();
+    let content = server.content.clone();
+    let running = server.running.clone();
+    let handed_over = server.handed_over.clone();
+    thread::Builder::new()
+      .name("x11-selection-server".to_string())
+      .spawn(move || {
+        let _ = serve_requests(&conn, &content, &handed_over);
+        running.store(false, Ordering::SeqCst);
+        lock(&content).clear();
+      })?;
+    static AT_EXIT: Once = Once::new();
+    AT_EXIT.call_once(|| unsafe {
+      libc::atexit(hand_over_at_exit);
+    });
+    Ok(server)
+  }
+
+  /// Asks the clipboard manager, if one runs, to save the `CLIPBOARD` content this process owns
+  /// (`SAVE_TARGETS` conversion of `CLIPBOARD_MANAGER`, as arboard does), and waits until it is
+  /// saved or [`HANDOVER_TIMEOUT`] elapses
+  fn hand_over(&self) -> X11Result<()> {
+    let clipboard = intern_atom(&self.conn, "CLIPBOARD")?;
+    let manager = intern_atom(&self.conn, "CLIPBOARD_MANAGER")?;
+    let save_targets = intern_atom(&self.conn, "SAVE_TARGETS")?;
+    let is_served = lock(&self.content)
+      .iter()
+      .any(|served| served.selection == clipboard);
+    if !is_served
+      || self.conn.get_selection_owner(clipboard)?.reply()?.owner != self.window
+      || self.conn.get_selection_owner(manager)?.reply()?.owner == NONE
+    {
+      return Ok(());
+    }
+    self.handed_over.reset();
+    self
+      .conn
+      .convert_selection(self.window, manager, save_targets, NONE, CURRENT_TIME)?;
+    self.conn.flush()?;
+    self.handed_over.wait(HANDOVER_TIMEOUT);
+    Ok(())
+  }
+
+  fn serve(&self, selection: &str, targets: Vec<SelectionTarget>) -> X11Result<SelectionRead> {
+    let selection = intern_atom(&self.conn, selection)?;
+    let targets = targets
+      .into_iter()
+      .map(|target| Ok((intern_atom(&self.conn, &target.name)?, target)))
+      .collect::<X11Result<Vec<_>>>()?;
+    let resource_id_mask = self.conn.setup().resource_id_mask;
+    let reader = active_window_id(&self.conn, self.root)?.map(|window| window & !resource_id_mask);
+    let read = Arc::new(Signal::default());
+    {
+      let mut content = lock(&self.content);
+      content.retain(|served| served.selection != selection);
+      content.push(ServedSelection {
+        selection,
+        targets,
+        reader,
+        read: read.clone(),
+      });
+    }
+    self
+      .conn
+      .set_selection_owner(self.window, selection, CURRENT_TIME)?
+      .check()?;
+    if self.conn.get_selection_owner(selection)?.reply()?.owner != self.window {
+      lock(&self.content).retain(|served| served.selection != selection);
+      return Err("Failed to own the selection".into());
+    }
+    Ok(SelectionRead(read))
+  }
+
+  fn clear(&self, selection: &str) -> X11Result<()> {
+    let selection = intern_atom(&self.conn, selection)?;
+    lock(&self.content).retain(|served| served.selection != selection);
+    self
+      .conn
+      .set_selection_owner(NONE, selection, CURRENT_TIME)?
+      .check()?;
+    Ok(())
+  }
+}
+
+/// Serves the content of the selections owned to the apps that request it, until the connection
+/// to the X server is lost
+fn serve_requests(
+  conn: &RustConnection,
+  content: &Mutex<Vec<ServedSelection>>,
+  handed_over: &Signal,
+) -> X11Result<()> {
+  let atoms = ServerAtoms::intern(conn)?;
+  loop {
+    match conn.wait_for_event()? {
+      Event::SelectionRequest(request) => reply_to_request(conn, &request, &atoms, &lock(content))?,
+      // The selection may have been owned again since, as the owner is set from other threads
+      Event::SelectionClear(event)
+        if conn.get_selection_owner(event.selection)?.reply()?.owner != event.owner =>
+      {
+        lock(content).retain(|served| served.selection != event.selection);
+      }
+      // The clipboard manager is done saving the clipboard content, or refuses to
+      Event::SelectionNotify(event) if event.target == atoms.save_targets => handed_over.set(),
+      _ => {}
+    }
+  }
+}
+
+/// Sends the selection content converted to the requested target to the requestor, or refuses the
+/// conversion if it is not offered
+fn reply_to_request(
+  conn: &RustConnection,
+  request: &SelectionRequestEvent,
+  atoms: &ServerAtoms,
+  content: &[ServedSelection],
+) -> X11Result<()> {
+  // Obsolete clients set no property, in which case the target is used as property
+  let property = match request.property {
+    NONE => request.target,
+    property => property,
+  };
+  let served = content
+    .iter()
+    .find(|served| served.selection == request.selection);
+  let (is_sent, is_read) = match served {
+    // `MULTIPLE` requires the property listing the targets to convert to
+    Some(served) if request.target == atoms.multiple && request.property != NONE => {
+      send_multiple(conn, request.requestor, property, atoms, served)?
+    }
+    Some(served) => {
+      let is_sent = send_target(
+        conn,
+        request.requestor,
+        property,
+        request.target,
+        atoms,
+        served,
+      )?;
+      (is_sent, is_sent && request.target != atoms.targets)
+    }
+    None => (false, false),
+  };
+  let notify = SelectionNotifyEvent {
+    response_type: SELECTION_NOTIFY_EVENT,
+    sequence: 0,
+    time: request.time,
+    requestor: request.requestor,
+    selection: request.selection,
+    target: request.target,
+    property: if is_sent { property } else { NONE },
+  };
+  conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+  conn.flush()?;
+
+  // The content is read once sent in a target other than `TARGETS`, which only lists them. Requests
+  // of the history exclusion marker by clipboard managers come from other clients than the reader
+  if let Some(served) = served.filter(|_| is_read) {
+    let resource_id_mask = conn.setup().resource_id_mask;
+    if served
+      .reader
+      .is_none_or(|reader| request.requestor & !resource_id_mask == reader)
+    {
+      served.read.set();
+    }
+  }
+  Ok(())
+}
+
+/// Sets the property of the requestor to the content converted to the target, or to the list of
+/// targets for `TARGETS`. Returns whether it is set, i.e. the target is offered
+fn send_target(
+  conn: &RustConnection,
+  requestor: Window,
+  property: Atom,
+  target: Atom,
+  atoms: &ServerAtoms,
+  served: &ServedSelection,
+) -> X11Result<bool> {
+  if target == atoms.targets {
+    let targets: Vec<Atom> = [atoms.targets, atoms.multiple]
+      .into_iter()
+      .chain(served.targets.iter().map(|(atom, _)| *atom))
+      .collect();
+    conn.change_property32(
+      PropMode::REPLACE,
+      requestor,
+      property,
+      AtomEnum::ATOM,
+      &targets,
+    )?;
+    return Ok(true);
+  }
+  match served.targets.iter().find(|(atom, _)| *atom == target) {
+    // Content too large for a single request would need an `INCR` transfer
+    Some((atom, target)) if target.data.len() + 24 <= conn.maximum_request_bytes() => {
+      let type_ = match target.type_ {
+        NONE => *atom,
+        type_ => type_,
+      };
+      conn.change_property(
+        PropMode::REPLACE,
+        requestor,
+        property,
+        type_,
+        target.format,
+        (target.data.len() / usize::from(target.format / 8)) as u32,
+        &target.data,
+      )?;
+      Ok(true)
+    }
+    _ => Ok(false),
+  }
+}
+
+/// Converts the content to each target of the (target, property) pairs listed in the property of
+/// the requestor, for a `MULTIPLE` request (e.g. by a clipboard manager saving the content). The
+/// targets that are not offered are replaced by `NONE` in the list. Returns whether the list is
+/// read, and whether the content is read in a target other than `TARGETS`
+fn send_multiple(
+  conn: &RustConnection,
+  requestor: Window,
+  property: Atom,
+  atoms: &ServerAtoms,
+  served: &ServedSelection,
+) -> X11Result<(bool, bool)> {
+  let reply = conn
+    .get_property(false, requestor, property, AtomEnum::ANY, 0, u32::MAX / 4)?
+    .reply()?;
+  let Some(pairs) = reply.value32() else {
+    return Ok((false, false));
+  };
+  let mut pairs: Vec<Atom> = pairs.collect();
+  let mut is_read = false;
+  for pair in pairs.chunks_exact_mut(2) {
+    if send_target(conn, requestor, pair[1], pair[0], atoms, served)? {
+      is_read |= pair[0] != atoms.targets;
+    } else {
+      pair[0] = NONE;
+    }
+  }
+  conn.change_property32(PropMode::REPLACE, requestor, property, reply.type_, &pairs)?;
+  Ok((true, is_read))
+}
+
+/// Listener of the owner changes of a selection (e.g. `PRIMARY`) through the XFIXES extension. A
+/// selection changes owner whenever an app sets its content, e.g. the user highlights text, so its
+/// content changes can be told without reading it over and over
+pub struct SelectionOwnerListener {
+  conn: RustConnection,
+}
+
+impl SelectionOwnerListener {
+  /// ##### Errors
+  /// * `CLIPBOARD_UNAVAILABLE` - The X server could not be connected to
+  /// * `STRATEGY_UNSUPPORTED` - The X server does not support the XFIXES extension
+  pub fn new(selection: &str) -> Result<Self> {
+    let (conn, screen_num) = connect().with_code(ErrorCode::ClipboardUnavailable)?;
+    conn
+      .xfixes_query_version(5, 0)
+      .with_code(ErrorCode::StrategyUnsupported)?
+      .reply()
+      .with_code(ErrorCode::StrategyUnsupported)?;
+    let window = create_window(&conn, screen_num).with_code(ErrorCode::ClipboardUnavailable)?;
+    let selection = intern_atom(&conn, selection).with_code(ErrorCode::ClipboardUnavailable)?;
+    conn
+      .xfixes_select_selection_input(
+        window,
+        selection,
+        SelectionEventMask::SET_SELECTION_OWNER
+          | SelectionEventMask::SELECTION_WINDOW_DESTROY
+          | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+      )
+      .with_code(ErrorCode::ClipboardUnavailable)?
+      .check()
+      .with_code(ErrorCode::ClipboardUnavailable)?;
+    Ok(SelectionOwnerListener { conn })
+  }
+
+  /// Returns whether the selection changed owner since the last call, without blocking
+  pub fn poll(&self) -> Result<bool> {
+    let mut changed = false;
+    while let Some(event) = self
+      .conn
+      .poll_for_event()
+      .with_code(ErrorCode::ClipboardUnavailable)?
+    {
+      changed |= matches!(event, Event::XfixesSelectionNotify(_));
+    }
+    Ok(changed)
+  }
+}
+
+/// Window that has the input focus, as reported by the window manager
+#[derive(Debug, Default)]
+pub struct ActiveWindow {
+  /// Title (`_NET_WM_NAME`, or `WM_NAME` if unset)
+  pub title: Option<String>,
+  /// Instance and class names (`WM_CLASS`), e.g. `["gnome-terminal-server", "Gnome-terminal"]`
+  pub class: Vec<String>,
+  /// Id of the process that owns the window (`_NET_WM_PID`)
+  pub pid: Option<u32>,
+}
+
+fn active_window_with(conn: &RustConnection, root: Window) -> X11Result<Option<ActiveWindow>> {
+  let Some(window) = active_window_id(conn, root)? else {
+    return Ok(None);
+  };
+
+  let net_wm_name = intern_atom(conn, "_NET_WM_NAME")?;
+  let utf8_string = intern_atom(conn, "UTF8_STRING")?;
+  let net_wm_pid = intern_atom(conn, "_NET_WM_PID")?;
+  let title = match read_string_property(conn, window, net_wm_name, utf8_string)? {
+    Some(title) => Some(title),
+    None => read_string_property(conn, window, AtomEnum::WM_NAME.into(), AtomEnum::ANY.into())?,
+  };
+  let class = read_string_property(
+    conn,
+    window,
+    AtomEnum::WM_CLASS.into(),
+    AtomEnum::STRING.into(),
+  )?
+  .map(|class| {
+    class
+      .split('\0')
+      .filter(|name| !name.is_empty())
+      .map(str::to_string)
+      .collect()
+  })
+  .unwrap_or_default();
+  let pid = conn
+    .get_property(false, window, net_wm_pid, AtomEnum::CARDINAL, 0, 1)?
+    .reply()?
+    .value32()
+    .and_then(|mut values| values.next());
+  Ok(Some(ActiveWindow { title, class, pid }))
+}
+
+/// Returns the window the window manager reports as active (EWMH `_NET_ACTIVE_WINDOW`), if any
+fn active_window_id(conn: &RustConnection, root: Window) -> X11Result<Option<Window>> {
+  let active_window = intern_atom(conn, "_NET_ACTIVE_WINDOW")?;
+  let window = conn
+    .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)?
+    .reply()?
+    .value32()
+    .and_then(|mut values| values.next());
+  Ok(window.filter(|window| *window != NONE))
+}
+
+fn read_string_property(
+  conn: &RustConnection,
+  window: Window,
+  property: Atom,
+  type_: Atom,
+) -> X11Result<Option<String>> {
+  let reply = conn
+    .get_property(false, window, property, type_, 0, u32::MAX / 4)?
+    .reply()?;
+  Ok((reply.type_ != NONE).then(|| String::from_utf8_lossy(&reply.value).into_owned()))
+}
+
+/// Keyboard that simulates physical key presses by X keycode through the XTEST extension, so the
+/// pressed key does not depend on the active keyboard layout
+pub struct XTestKeyboard {
+  conn: RustConnection,
+  root: Window,
+}
+
+impl XTestKeyboard {
+  pub fn new() -> Result<Self> {
+    let (conn, screen_num) = connect().with_code(ErrorCode::KeyboardInitFailed)?;
+    let root = conn.setup().roots[screen_num].root;
+    Ok(XTestKeyboard { conn, root })
+  }
+
+  /// Presses or releases the key. It waits for the X server to process the event, so events sent
+  /// right after it from other connections (e.g. enigo) cannot overtake it
+  pub fn key(&self, keycode: u8, press: bool) -> Result<()> {
+    let event_type = if press {
+      x11rb::protocol::xproto::KEY_PRESS_EVENT
+    } else {
+      x11rb::protocol::xproto::KEY_RELEASE_EVENT
+    };
+    self
+      .conn
+      .xtest_fake_input(event_type, keycode, CURRENT_TIME, self.root, 0, 0, 0)
+      .with_code(ErrorCode::KeySimulationFailed)?
+      .check()
+      .with_code(ErrorCode::KeySimulationFailed)
+  }
+
+  /// Returns whether the connection to the X server still works, with a round trip
+  pub fn is_connected(&self) -> bool {
+    self
+      .conn
+      .get_input_focus()
+      .is_ok_and(|cookie| cookie.reply().is_ok())
+  }
+
+  /// Returns the window the window manager reports as active, if any
+  pub fn active_window(&self) -> Option<Window> {
+    active_window_id(&self.conn, self.root).ok().flatten()
+  }
+
+  /// Returns the given keys that are currently held down
+  pub fn held_keys(&self, keycodes: &[u8]) -> Result<Vec<u8>> {
+    let keymap = self
+      .conn
+      .query_keymap()
+      .with_code(ErrorCode::KeySimulationFailed)?
+      .reply()
+      .with_code(ErrorCode::KeySimulationFailed)?
+      .keys;
+    Ok(
+      keycodes
+        .iter()
+        .copied()
+        .filter(|keycode| is_key_down(&keymap, *keycode))
+        .collect(),
+    )
+  }
+
+  /// Returns the given keys the user is physically holding down, from the key state of each
+  /// keyboard device (XInput), which leaves out the XTEST device the simulated keys are sent from
+  pub fn physically_held_keys(&self, keycodes: &[u8]) -> Result<Vec<u8>> {
+    self
+      .physically_held_keys_with(keycodes)
+      .with_code(ErrorCode::KeySimulationFailed)
+  }
+
+  fn physically_held_keys_with(&self, keycodes: &[u8]) -> X11Result<Vec<u8>> {
+    let devices = self.conn.xinput_list_input_devices()?.reply()?;
+    let mut held_keys = Vec::new();
+    // Physical keyboards are extension devices attached to the core keyboard
+    let keyboards = devices
+      .devices
+      .iter()
+      .zip(&devices.names)
+      .filter(|(device, name)| {
+        device.device_use == DeviceUse::IS_X_EXTENSION_KEYBOARD
+          && !name.name.ends_with(XTEST_DEVICE_SUFFIX)
+      });
+    for (device, _) in keyboards {
+      self.conn.xinput_open_device(device.device_id)?.reply()?;
+      let state = self.conn.xinput_query_device_state(device.device_id);
+      self.conn.xinput_close_device(device.device_id)?;
+      for class in state?.reply()?.classes {
+        if let InputStateData::Key(key_state) = class.data {
+          held_keys.extend(
+            keycodes
+              .iter()
+              .filter(|keycode| is_key_down(&key_state.keys, **keycode)),
+          );
+        }
+      }
+    }
+    held_keys.sort_unstable();
+    held_keys.dedup();
+    Ok(held_keys)
+  }
+}
+
+/// Name suffix of the keyboard device the X server sends the XTEST key events from, e.g. `Virtual
+/// core XTEST keyboard`
+const XTEST_DEVICE_SUFFIX: &[u8] = b"XTEST keyboard";
+
+/// Returns whether the key is down in the key state bit vector of a keyboard
+fn is_key_down(keys: &[u8; 32], keycode: u8) -> bool {
+  keys[usize::from(keycode / 8)] & (1 << (keycode % 8)) != 0
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, process::Command};
+  use x11rb::protocol::xproto::{EventMask, InputFocus, KeyButMask};
+
+  use super::*;
+  use crate::keycode;
+
+  /// Keyboard layout of the X session, as read by `setxkbmap -query`, set back when dropped
+  struct SavedLayout {
+    args: Vec<String>,
+  }
+
+  impl SavedLayout {
+    fn query() -> Option<Self> {
+      let output = Command::new("setxkbmap").arg("-query").output().ok()?;
+      let query = String::from_utf8(output.stdout).ok()?;
+      // Clear the options first, as the given ones are appended to the current ones
+      let mut args = vec!["-option".to_string(), String::new()];
+      for line in query.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+          continue;
+        };
+        let name = match name {
+          "layout" | "variant" | "model" => name,
+          "options" => "option",
+          _ => continue,
+        };
+        args.extend([format!("-{name}"), value.trim().to_string()]);
+      }
+      Some(SavedLayout { args })
+    }
+  }
+
+  impl Drop for SavedLayout {
+    fn drop(&mut self) {
+      let _ = Command::new("setxkbmap").args(&self.args).status();
+    }
+  }
+
+  /// Serves content in several targets and reads it back whole. It sets the clipboard content of
+  /// the X session, so it is ignored by default like [`sends_physical_keys_with_any_layout`]
+  #[test]
+  #[ignore = "sets the clipboard content of the X session"]
+  fn serves_every_target_read() {
+    assert!(env::var_os("DISPLAY").is_some(), "no X server to run on");
+    let content: [(&str, &[u8]); 3] = [
+      ("text/plain;charset=utf-8", b"Hello"),
+      ("text/html", b"<b>Hello</b>"),
+      ("application/x-custom", &[0, 1, 2, 255]),
+    ];
+    let targets = content
+      .iter()
+      .map(|(name, data)| SelectionTarget::new(name, data.to_vec()))
+      .collect();
+    let served = serve_selection("CLIPBOARD", targets).unwrap();
+    assert!(!served.wait(Duration::ZERO));
+
+    let x11 = X11Connection::new().unwrap();
+    let targets = x11.read_all_targets("CLIPBOARD").unwrap().unwrap();
+    // Without a window manager, no window is focused, so the read of any client is told
+    assert!(served.wait(Duration::ZERO));
+    let read: Vec<_> = targets
+      .iter()
+      .map(|target| (target.name.as_str(), target.data.as_slice()))
+      .collect();
+    assert_eq!(read, content);
+
+    clear_selection("CLIPBOARD").unwrap();
+    assert!(x11
+      .read_all_targets("CLIPBOARD")
+      .unwrap()
+      .unwrap()
+      .is_empty());
+  }
+
+  /// Hands the served clipboard content over to a clipboard manager, which reads it when asked to
+  /// save it. It sets the clipboard content of the X session, so it is ignored by default like
+  /// [`serves_every_target_read`]
+  #[test]
+  #[ignore = "sets the clipboard content and the clipboard manager of the X session"]
+  fn hands_the_clipboard_over_to_the_manager() {
+    assert!(env::var_os("DISPLAY").is_some(), "no X server to run on");
+    let manager = X11Connection::new().unwrap();
+    let manager_atom = intern_atom(&manager.conn, "CLIPBOARD_MANAGER").unwrap();
+    let save_targets = intern_atom(&manager.conn, "SAVE_TARGETS").unwrap();
+    manager
+      .conn
+      .set_selection_owner(manager.window, manager_atom, CURRENT_TIME)
+      .unwrap();
+    manager.conn.flush().unwrap();
+    let saving = thread::spawn(move || loop {
+      let Event::SelectionRequest(request) = manager.conn.wait_for_event().unwrap() else {
+        continue;
+      };
+      assert_eq!(request.target, save_targets);
+      let reader = X11Connection::new().unwrap();
+      let saved = reader.read_selection("CLIPBOARD", "text/plain").unwrap();
+      let notify = SelectionNotifyEvent {
+        response_type: SELECTION_NOTIFY_EVENT,
+        sequence: 0,
+        time: request.time,
+        requestor: request.requestor,
+        selection: request.selection,
+        target: request.target,
+        property: request.property,
+      };
+      manager
+        .conn
+        .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+        .unwrap();
+      manager.conn.flush().unwrap();
+      return saved;
+    });
+
+    let targets = vec![SelectionTarget::new("text/plain", b"Hello".to_vec())];
+    serve_selection("CLIPBOARD", targets).unwrap();
+    let started = Instant::now();
+    hand_over_clipboard().unwrap();
+    assert!(started.elapsed() < HANDOVER_TIMEOUT);
+    assert_eq!(saving.join().unwrap().as_deref(), Some(&b"Hello"[..]));
+    clear_selection("CLIPBOARD").unwrap();
+  }
+
+  /// Sends the copy shortcut after switching the keyboard layout with `setxkbmap`, and checks the
+  /// physical `Ctrl + C` keys are pressed with every layout. It switches the layout of the whole X
+  /// session and injects `Ctrl + C` into it, so it is ignored by default and only meant to be run
+  /// against a throwaway X server, e.g. `xvfb-run cargo test -- --include-ignored`
+  #[test]
+  #[ignore = "switches the keyboard layout of the X session and injects Ctrl + C into it"]
+  fn sends_physical_keys_with_any_layout() {
+    assert!(env::var_os("DISPLAY").is_some(), "no X server to run on");
+    let _saved_layout = SavedLayout::query().expect("setxkbmap is not available");
+    let (conn, screen_num) = x11rb::connect(None).unwrap();
+    let root = conn.setup().roots[screen_num].root;
+    let window = conn.generate_id().unwrap();
+    conn
+      .create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        10,
+        10,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new().event_mask(EventMask::KEY_PRESS),
+      )
+      .unwrap();
+    conn.map_window(window).unwrap().check().unwrap();
+    conn
+      .set_input_focus(InputFocus::POINTER_ROOT, window, CURRENT_TIME)
+      .unwrap()
+      .check()
+      .unwrap();
+
+    let layouts: [&[&str]; 5] = [
+      &["us"],
+      &["ru"],
+      &["gr"],
+      &["il"],
+      &["us", "-variant", "dvorak"],
+    ];
+    for layout in layouts {
+      let status = Command::new("setxkbmap")
+        .arg("-layout")
+        .args(layout)
+        .status()
+        .unwrap();
+      assert!(status.success(), "setxkbmap {layout:?} failed");
+
+      let keyboard = XTestKeyboard::new().unwrap();
+      keyboard.key(keycode::COPY_MODIFIER as u8, true).unwrap();
+      keyboard.key(keycode::C as u8, true).unwrap();
+      keyboard.key(keycode::C as u8, false).unwrap();
+      keyboard.key(keycode::COPY_MODIFIER as u8, false).unwrap();
+
+      let deadline = Instant::now() + Duration::from_secs(1);
+      let c_press = loop {
+        match conn.poll_for_event().unwrap() {
+          Some(Event::KeyPress(event)) if event.detail == keycode::C as u8 => break event,
+          Some(_) => {}
+          None if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+          None => panic!("no C key press with layout {layout:?}"),
+        }
+      };
+      assert!(c_press.state.contains(KeyButMask::CONTROL));
+    }
+  }
+}
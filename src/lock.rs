@@ -0,0 +1,210 @@
+//! Serialization of the clipboard sequences (save, copy or paste, restore) across the process, so
+//! two calls at the same time (e.g. two hotkeys, or a worker thread and the JS thread) cannot
+//! interleave them and leave the wrong content on the clipboard
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, TryLockError};
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::selection::SelectionOptions;
+
+/// Held while the clipboard is saved, used and restored
+static CLIPBOARD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Async selection reads in progress, which calls with the same options join instead of copying
+/// the selection again
+static SHARED_READS: Mutex<Vec<Arc<SharedRead>>> = Mutex::new(Vec::new());
+
+/// Takes the process-wide clipboard lock, waiting until the call holding it is done, or failing
+/// with [`ErrorCode::Busy`] right away with `fail_if_busy`
+pub fn acquire(fail_if_busy: bool) -> Result<MutexGuard<'static, ()>> {
+  acquire_lock(&CLIPBOARD_LOCK, fail_if_busy)
+}
+
+fn acquire_lock(clipboard_lock: &Mutex<()>, fail_if_busy: bool) -> Result<MutexGuard<'_, ()>> {
+  if !fail_if_busy {
+    return Ok(lock(clipboard_lock));
+  }
+  match clipboard_lock.try_lock() {
+    Ok(guard) => Ok(guard),
+    Err(TryLockError::Poisoned(err)) => Ok(err.into_inner()),
+    Err(TryLockError::WouldBlock) => Err(Error::new(
+      ErrorCode::Busy,
+      "Another call is using the clipboard".to_string(),
+    )),
+  }
+}
+
+/// Selection read in progress, along with its result once done
+struct SharedRead {
+  options: SelectionOptions,
+  result: Mutex<Option<Result<String>>>,
+  done: Condvar,
+}
+
+/// Publishes the result of the shared read to the calls waiting for it. If the read panics, an
+/// error is published instead when it is dropped, so they do not wait forever
+struct SharedReadLeader(Arc<SharedRead>);
+
+impl SharedReadLeader {
+  fn publish(&self, result: Result<String>) {
+    lock(&SHARED_READS).retain(|other| !Arc::ptr_eq(other, &self.0));
+    let mut published = lock(&self.0.result);
+    if published.is_none() {
+      *published = Some(result);
+    }
+    self.0.done.notify_all();
+  }
+}
+
+impl Drop for SharedReadLeader {
+  fn drop(&mut self) {
+    self.publish(Err(Error::new(
+      ErrorCode::ClipboardUnavailable,
+      "The shared selection read panicked".to_string(),
+    )));
+  }
+}
+
+/// Runs the selection read, unless a read with the same options is already in progress, in which
+/// case it waits for that read and returns its result instead, so overlapping calls share a single
+/// copy
+pub fn share_read(
+  options: &SelectionOptions,
+  read: impl FnOnce() -> Result<String>,
+) -> Result<String> {
+  let (shared, is_leader) = {
+    let mut shared_reads = lock(&SHARED_READS);
+    match shared_reads
+      .iter()
+      .find(|shared| shared.options == *options)
+    {
+      Some(shared) => (shared.clone(), false),
+      None => {
+        let shared = Arc::new(SharedRead {
+          options: options.clone(),
+          result: Mutex::new(None),
+          done: Condvar::new(),
+        });
+        shared_reads.push(shared.clone());
+        (shared, true)
+      }
+    }
+  };
+  if !is_leader {
+    let mut result = lock(&shared.result);
+    while result.is_none() {
+      result = shared
+        .done
+        .wait(result)
+        .unwrap_or_else(|err| err.into_inner());
+    }
+    return copy_result(result.as_ref().expect("the shared read is done"));
+  }
+
+  let leader = SharedReadLeader(shared);
+  let result = read();
+  leader.publish(copy_result(&result));
+  result
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn copy_result(result: &Result<String>) -> Result<String> {
+  match result {
+    Ok(text) => Ok(text.clone()),
+    Err(err) => Err(Error::new(err.status, err.reason.clone())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    sync::{
+      atomic::{AtomicU32, Ordering},
+      Barrier,
+    },
+    thread,
+    time::Duration,
+  };
+
+  use super::*;
+
+  #[test]
+  fn fails_fast_while_locked() {
+    // Its own lock, as the other tests take the process-wide one
+    let clipboard_lock = Mutex::new(());
+    let guard = acquire_lock(&clipboard_lock, false).unwrap();
+    let err = acquire_lock(&clipboard_lock, true).unwrap_err();
+    assert_eq!(err.status, ErrorCode::Busy);
+    drop(guard);
+    assert!(acquire_lock(&clipboard_lock, true).is_ok());
+  }
+
+  #[test]
+  fn shares_overlapping_reads() {
+    let options = SelectionOptions {
+      max_length: Some(4242),
+      ..Default::default()
+    };
+    let reads = AtomicU32::new(0);
+    let barrier = Barrier::new(3);
+    let results: Vec<_> = thread::scope(|scope| {
+      let handles: Vec<_> = (0..3)
+        .map(|_| {
+          scope.spawn(|| {
+            barrier.wait();
+            share_read(&options, || {
+              reads.fetch_add(1, Ordering::SeqCst);
+              thread::sleep(Duration::from_millis(100));
+              Ok("Selected".to_string())
+            })
+          })
+        })
+        .collect();
+      handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap().unwrap())
+        .collect()
+    });
+    assert_eq!(results, ["Selected"; 3]);
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    // Reads with other options are not shared
+    let other_options = SelectionOptions {
+      max_length: Some(4243),
+      ..Default::default()
+    };
+    let text = share_read(&other_options, || Ok("Other".to_string())).unwrap();
+    assert_eq!(text, "Other");
+  }
+
+  #[test]
+  fn fails_shared_read_if_it_panics() {
+    let options = SelectionOptions {
+      max_length: Some(4244),
+      ..Default::default()
+    };
+    let barrier = Barrier::new(2);
+    let (leader_result, result) = thread::scope(|scope| {
+      let leader = scope.spawn(|| {
+        share_read(&options, || {
+          barrier.wait();
+          // Let the other call join the read before it panics
+          thread::sleep(Duration::from_millis(100));
+          panic!("The read panics");
+        })
+      });
+      barrier.wait();
+      let result = share_read(&options, || Ok("Not shared".to_string()));
+      (leader.join(), result)
+    });
+    assert!(leader_result.is_err());
+    assert_eq!(result.unwrap_err().status, ErrorCode::ClipboardUnavailable);
+
+    // The panicked read is not joined anymore
+    let text = share_read(&options, || Ok("Selected".to_string())).unwrap();
+    assert_eq!(text, "Selected");
+  }
+}
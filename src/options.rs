@@ -0,0 +1,321 @@
+use napi::Either;
+use regex::Regex;
+
+use crate::apps::{AppPattern, AppRules};
+use crate::error::{Error, ErrorCode, Result, WithErrorCode};
+use crate::formats::SelectionFormat;
+use crate::replace::{ReplaceOptions, DEFAULT_PASTE_WAIT_TIME_MS};
+use crate::selection::{
+  FocusChangePolicy, ModifierPolicy, SelectionOptions, SelectionStrategy, TerminalPolicy,
+  DEFAULT_COPY_WAIT_TIME_MS, DEFAULT_MODIFIER_TIMEOUT_MS, DEFAULT_POLL_INTERVAL_MS,
+  DEFAULT_TIMEOUT_MS,
+};
+use crate::shortcut::Shortcut;
+use crate::typing::{TypeOptions, DEFAULT_CHUNK_SIZE};
+
+/// Options to customize how `getSelectionText` retrieves the selection text
+#[napi(object)]
+#[derive(Default)]
+pub struct GetSelectionTextOptions {
+  /// The minimum time to wait after performing the copy operation before reading the clipboard
+  /// text. It defaults to 5ms.
+  pub copy_wait_time_ms: Option<u32>,
+  /// The maximum time to wait for the copied selection text to land on the clipboard. If nothing
+  /// is copied by then, e.g. there is no selection, an empty string is returned. It defaults to
+  /// 100ms, which works for most use cases. However, a larger value would be needed to support use
+  /// case for large selection text that takes longer to copy to the clipboard.
+  pub timeout_ms: Option<u32>,
+  /// How often the clipboard is read while waiting for the copied selection text. It defaults to
+  /// 5ms.
+  pub poll_interval_ms: Option<u32>,
+  /// Whether to restore the clipboard previous content after the copy, which returns the clipboard
+  /// to exactly its previous state (it is cleared if it was empty). If `false`, the copied
+  /// selection text is kept on the clipboard instead. It defaults to `true`.
+  pub restore_clipboard: Option<bool>,
+  /// How the selection text is retrieved: `'copy'` to copy it to the clipboard, or `'primary'` to
+  /// read the primary selection (Linux only). It defaults to `'primary'` in Linux when the primary
+  /// selection is readable and `'copy'` elsewhere.
+  #[napi(ts_type = "'copy' | 'primary'")]
+  pub strategy: Option<SelectionStrategy>,
+  /// The maximum number of characters to return. Longer selection text is truncated.
+  pub max_length: Option<u32>,
+  /// The rich text formats to read along with the text, only used by `getSelection`. It defaults to
+  /// `['html', 'rtf']`.
+  #[napi(ts_type = "Array<'html' | 'rtf'>")]
+  pub formats: Option<Vec<SelectionFormat>>,
+  /// What to do with modifier keys the user is still holding down when the copy shortcut is
+  /// simulated, e.g. `Alt + Shift` of the global hotkey that triggered the call, as they would turn
+  /// `Ctrl + C` into `Ctrl + Alt + Shift + C`: `'release'` to release them before the copy and
+  /// press them back after it if the user is still holding them down (which is only told in Mac
+  /// and Linux X11, so they are left released in Windows and Wayland), `'wait'` to wait until the
+  /// user releases them (they are released as with `'release'` if still held down after
+  /// `modifierTimeoutMs`), or `'ignore'` to leave them as they are. It defaults to `'release'`.
+  #[napi(ts_type = "'release' | 'wait' | 'ignore'")]
+  pub modifier_policy: Option<ModifierPolicy>,
+  /// The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  /// policy. It defaults to 1000ms.
+  pub modifier_timeout_ms: Option<u32>,
+  /// The shortcut that copies the selection, as a key-chord description like `'Ctrl+Shift+C'`
+  /// (e.g. for terminal emulators, where `Ctrl+C` interrupts the running program) or
+  /// `'Ctrl+Insert'`, i.e. modifier keys (`Ctrl`, `Shift`, `Alt`, `Cmd` or `CmdOrCtrl`) followed by
+  /// a key (a letter, digit, `F1` to `F12`, `Insert`, `Delete`, `Enter`, `Tab`, `Space` or `Escape`)
+  /// joined with `+`. A list of shortcuts can be given to try them in order until one copies the
+  /// selection, each waiting up to `timeoutMs`. It defaults to `'CmdOrCtrl+C'`.
+  #[napi(ts_type = "string | Array<string>")]
+  pub copy_shortcut: Option<Either<String, Vec<String>>>,
+  /// What to do with the `'copy'` strategy when the focused window is a terminal emulator (e.g.
+  /// xterm, GNOME Terminal, Konsole, Alacritty, kitty or WezTerm), where `Ctrl+C` interrupts the
+  /// running program: `'primary'` to read the primary selection instead, `'shortcut'` to copy with
+  /// `Ctrl+Shift+C` instead of `copyShortcut`, or `'ignore'` to copy as in any other window. The
+  /// focused window is only told in Linux X11 sessions. It defaults to `'primary'`.
+  #[napi(ts_type = "'primary' | 'shortcut' | 'ignore'")]
+  pub terminal_policy: Option<TerminalPolicy>,
+  /// Names of terminal emulators to detect in addition to the known ones, matched (case
+  /// insensitive) against the focused window `WM_CLASS` instance and class names and its process
+  /// name
+  pub terminals: Option<Vec<String>>,
+  /// What to do with the `'copy'` strategy when the input focus moves to another window during the
+  /// copy, e.g. the user switches windows right after triggering it, as the copied text could come
+  /// from the wrong window: `'abort'` to throw a `FOCUS_CHANGED` error, `'retry'` to copy the
+  /// selection of the newly focused window instead (up to 2 more times, then it throws as with
+  /// `'abort'`), or `'ignore'` to return the copied text anyway. The focused window is only told in
+  /// Linux X11 and Windows. It defaults to `'abort'`.
+  #[napi(ts_type = "'abort' | 'retry' | 'ignore'")]
+  pub focus_change_policy: Option<FocusChangePolicy>,
+  /// Whether to mark the restored clipboard content with the hints clipboard managers (e.g. Maccy,
+  /// Windows clipboard history) check to not record it, so restoring the clipboard does not add a
+  /// new history entry. In Linux, the only hint marks the content as a password, so the content is
+  /// restored without it. It defaults to `true`.
+  pub exclude_from_history: Option<bool>,
+  /// Whether to throw a `CLIPBOARD_CONCEALED` error with the `'copy'` strategy, before any keyboard
+  /// input is simulated, if the clipboard holds content marked concealed (e.g. a password copied
+  /// from a password manager), instead of saving and restoring it. It defaults to `false`.
+  pub skip_if_concealed: Option<bool>,
+  /// Apps to never retrieve the selection from, e.g. password managers, banking apps or terminal
+  /// emulators running `ssh`. If the focused app matches one of them, an `APP_DENIED` error is
+  /// thrown before the clipboard is touched or any keyboard input is simulated, as it is if the
+  /// focused app cannot be told (e.g. in Linux Wayland sessions), so no denied app is missed.
+  pub deny_apps: Option<Vec<AppMatcher>>,
+  /// Apps to only retrieve the selection from. If the focused app matches none of them, or it
+  /// cannot be told, an `APP_DENIED` error is thrown as with `denyApps`, which takes precedence.
+  /// It defaults to allowing every app.
+  pub allow_apps: Option<Vec<AppMatcher>>,
+  /// Whether to throw a `BUSY` error right away if another call (e.g. from another hotkey or a
+  /// worker thread) is using the clipboard, instead of waiting for it to finish, as only one call
+  /// at a time saves, uses and restores the clipboard. It defaults to `false`.
+  pub fail_if_busy: Option<bool>,
+}
+
+/// Description of an app for `denyApps` and `allowApps`, matched against the focused window. Every
+/// property that is set must match
+#[napi(object)]
+#[derive(Default)]
+pub struct AppMatcher {
+  /// The window class, matched (case insensitive) against the `WM_CLASS` instance and class names
+  /// in Linux X11, the window class name in Windows, or the app bundle identifier and name in Mac,
+  /// e.g. `'keepassxc'`
+  pub class: Option<String>,
+  /// A regular expression searched for in the window title, e.g. `'^ssh '`
+  pub title: Option<String>,
+  /// The path of the app executable, e.g. `'/usr/bin/keepassxc'`, or only its file name, e.g.
+  /// `'keepassxc'`
+  pub exe_path: Option<String>,
+}
+
+impl TryFrom<AppMatcher> for AppPattern {
+  type Error = Error;
+
+  fn try_from(matcher: AppMatcher) -> Result<Self> {
+    let title = matcher
+      .title
+      .map(|title| Regex::new(&title))
+      .transpose()
+      .with_code(ErrorCode::InvalidAppMatcher)?;
+    Ok(AppPattern {
+      class: matcher.class,
+      title,
+      exe_path: matcher.exe_path,
+    })
+  }
+}
+
+fn app_patterns(matchers: Vec<AppMatcher>) -> Result<Vec<AppPattern>> {
+  matchers.into_iter().map(AppPattern::try_from).collect()
+}
+
+impl TryFrom<GetSelectionTextOptions> for SelectionOptions {
+  type Error = Error;
+
+  fn try_from(options: GetSelectionTextOptions) -> Result<Self> {
+    let copy_shortcuts = match options.copy_shortcut {
+      Some(Either::A(description)) => vec![description.parse()?],
+      Some(Either::B(descriptions)) => descriptions
+        .iter()
+        .map(|description| description.parse())
+        .collect::<Result<_>>()?,
+      None => vec![Shortcut::copy()],
+    };
+    Ok(SelectionOptions {
+      strategy: options.strategy.unwrap_or_default(),
+      copy_wait_time_ms: options
+        .copy_wait_time_ms
+        .unwrap_or(DEFAULT_COPY_WAIT_TIME_MS),
+      timeout_ms: options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+      poll_interval_ms: options.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+      restore_clipboard: options.restore_clipboard.unwrap_or(true),
+      max_length: options.max_length,
+      formats: options
+        .formats
+        .unwrap_or_else(|| vec![SelectionFormat::Html, SelectionFormat::Rtf]),
+      modifier_policy: options.modifier_policy.unwrap_or_default(),
+      modifier_timeout_ms: options
+        .modifier_timeout_ms
+        .unwrap_or(DEFAULT_MODIFIER_TIMEOUT_MS),
+      copy_shortcuts,
+      terminal_policy: options.terminal_policy.unwrap_or_default(),
+      terminals: options.terminals.unwrap_or_default(),
+      focus_change_policy: options.focus_change_policy.unwrap_or_default(),
+      exclude_from_history: options.exclude_from_history.unwrap_or(true),
+      skip_if_concealed: options.skip_if_concealed.unwrap_or(false),
+      app_rules: AppRules {
+        allow: options.allow_apps.map(app_patterns).transpose()?,
+        deny: app_patterns(options.deny_apps.unwrap_or_default())?,
+      },
+      fail_if_busy: options.fail_if_busy.unwrap_or(false),
+    })
+  }
+}
+
+/// Options to customize how `replaceSelectionText` pastes the text
+#[napi(object)]
+#[derive(Default)]
+pub struct ReplaceSelectionTextOptions {
+  /// The maximum time to wait after simulating the paste shortcut before restoring the clipboard,
+  /// as the focused app reads the clipboard after handling the shortcut. In Linux, the clipboard
+  /// is restored as soon as the app reads the pasted text (in Wayland, only with
+  /// `excludeFromHistory`), while in Windows and Mac the whole time is waited for. If it is too
+  /// short, the app may paste the restored clipboard content instead. It defaults to 200ms.
+  pub paste_wait_time_ms: Option<u32>,
+  /// Whether to restore the clipboard previous content after the paste. If `false`, the pasted
+  /// text is kept on the clipboard instead. It defaults to `true`.
+  pub restore_clipboard: Option<bool>,
+  /// What to do with modifier keys the user is still holding down when the paste shortcut is
+  /// simulated, same as the `getSelectionText` option. It defaults to `'release'`.
+  #[napi(ts_type = "'release' | 'wait' | 'ignore'")]
+  pub modifier_policy: Option<ModifierPolicy>,
+  /// The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  /// policy. It defaults to 1000ms.
+  pub modifier_timeout_ms: Option<u32>,
+  /// The shortcut that pastes the clipboard, as a key-chord description like `'Shift+Insert'`, same
+  /// as the `copyShortcut` option of `getSelectionText`. It defaults to `'CmdOrCtrl+V'`.
+  pub paste_shortcut: Option<String>,
+  /// What to do when the focused window is a terminal emulator, where `Ctrl+V` is sent to the
+  /// running program: `'shortcut'` to paste with `Ctrl+Shift+V` instead of `pasteShortcut`, or
+  /// `'ignore'` to paste as in any other window. The focused window is only told in Linux X11
+  /// sessions. It defaults to `'shortcut'`.
+  #[napi(ts_type = "'shortcut' | 'ignore'")]
+  pub terminal_policy: Option<TerminalPolicy>,
+  /// Names of terminal emulators to detect in addition to the known ones, same as the
+  /// `getSelectionText` option
+  pub terminals: Option<Vec<String>>,
+  /// Whether to mark the pasted text and the restored clipboard content with the hints clipboard
+  /// managers check to not record them, same as the `getSelectionText` option (in Linux, only the
+  /// pasted text is marked). It defaults to `true`.
+  pub exclude_from_history: Option<bool>,
+  /// Whether to throw a `CLIPBOARD_CONCEALED` error, before any keyboard input is simulated, if the
+  /// clipboard holds content marked concealed, same as the `getSelectionText` option. It defaults
+  /// to `false`.
+  pub skip_if_concealed: Option<bool>,
+  /// Whether to throw a `BUSY` error right away if another call is using the clipboard, instead of
+  /// waiting for it to finish, same as the `getSelectionText` option. It defaults to `false`.
+  pub fail_if_busy: Option<bool>,
+}
+
+impl TryFrom<ReplaceSelectionTextOptions> for ReplaceOptions {
+  type Error = Error;
+
+  fn try_from(options: ReplaceSelectionTextOptions) -> Result<Self> {
+    Ok(ReplaceOptions {
+      paste_wait_time_ms: options
+        .paste_wait_time_ms
+        .unwrap_or(DEFAULT_PASTE_WAIT_TIME_MS),
+      restore_clipboard: options.restore_clipboard.unwrap_or(true),
+      modifier_policy: options.modifier_policy.unwrap_or_default(),
+      modifier_timeout_ms: options
+        .modifier_timeout_ms
+        .unwrap_or(DEFAULT_MODIFIER_TIMEOUT_MS),
+      paste_shortcut: match options.paste_shortcut {
+        Some(description) => description.parse()?,
+        None => Shortcut::paste(),
+      },
+      terminal_policy: options.terminal_policy.unwrap_or(TerminalPolicy::Shortcut),
+      terminals: options.terminals.unwrap_or_default(),
+      exclude_from_history: options.exclude_from_history.unwrap_or(true),
+      skip_if_concealed: options.skip_if_concealed.unwrap_or(false),
+      fail_if_busy: options.fail_if_busy.unwrap_or(false),
+    })
+  }
+}
+
+/// Options to customize how `typeText` types the text
+#[napi(object)]
+#[derive(Default)]
+pub struct TypeTextOptions {
+  /// The time to wait between chunks, e.g. for apps that drop keystrokes sent too fast. Set
+  /// `chunkSize` to 1 to wait between every character. It defaults to 0ms.
+  pub delay_ms: Option<u32>,
+  /// The maximum number of characters typed at once. The typing can only be cancelled between
+  /// chunks. It defaults to 50.
+  pub chunk_size: Option<u32>,
+  /// What to do with modifier keys the user is still holding down when the text is typed, as they
+  /// would turn the typed characters into shortcuts, same as the `getSelectionText` option. It
+  /// defaults to `'release'`.
+  #[napi(ts_type = "'release' | 'wait' | 'ignore'")]
+  pub modifier_policy: Option<ModifierPolicy>,
+  /// The maximum time to wait for held modifier keys to be released with the `'wait'` modifier
+  /// policy. It defaults to 1000ms.
+  pub modifier_timeout_ms: Option<u32>,
+}
+
+impl From<TypeTextOptions> for TypeOptions {
+  fn from(options: TypeTextOptions) -> Self {
+    TypeOptions {
+      delay_ms: options.delay_ms.unwrap_or(0),
+      chunk_size: options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE),
+      modifier_policy: options.modifier_policy.unwrap_or_default(),
+      modifier_timeout_ms: options
+        .modifier_timeout_ms
+        .unwrap_or(DEFAULT_MODIFIER_TIMEOUT_MS),
+    }
+  }
+}
+
+/// Options to customize how `watchSelection` reports the selection changes
+#[napi(object)]
+#[derive(Default)]
+pub struct WatchSelectionOptions {
+  /// How long the selection must stay unchanged before it is reported, so a selection being
+  /// extended (e.g. the user dragging over text) is reported once. It defaults to 150ms.
+  pub debounce_ms: Option<u32>,
+  /// The maximum number of characters of the reported text. Longer selection text is truncated.
+  pub max_length: Option<u32>,
+}
+
+/// Resolves the `getSelectionText` argument, which is either the options object or, for backward
+/// compatibility, the `copyWaitTimeMs` number
+///
+/// ##### Errors
+/// * `INVALID_SHORTCUT` - A copy shortcut description could not be parsed
+pub fn resolve_options(
+  options: Option<Either<u32, GetSelectionTextOptions>>,
+) -> Result<SelectionOptions> {
+  match options {
+    Some(Either::A(copy_wait_time_ms)) => GetSelectionTextOptions {
+      copy_wait_time_ms: Some(copy_wait_time_ms),
+      ..Default::default()
+    }
+    .try_into(),
+    Some(Either::B(options)) => options.try_into(),
+    None => GetSelectionTextOptions::default().try_into(),
+  }
+}
@@ -0,0 +1,116 @@
+use napi::{bindgen_prelude::AsyncTask, Either};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::backend::SystemBackends;
+use crate::error::{Error, ErrorCode, Result};
+use crate::options::{self, GetSelectionTextOptions};
+use crate::selection::{self, SelectionOptions};
+use crate::task::ReadSelectionTextTask;
+
+/// Options and system backends of a [`SelectionReader`], shared with its async reads
+pub struct ReaderState {
+  options: SelectionOptions,
+  /// Backends kept across reads, or `None` once the reader is closed
+  backends: Option<SystemBackends>,
+}
+
+impl ReaderState {
+  /// Retrieves the selection text with the kept backends. After a clipboard or keyboard failure,
+  /// which may come from a lost display connection, the backends are created again on next read
+  pub fn read(&mut self) -> Result<String> {
+    let Some(backends) = &mut self.backends else {
+      return Err(Error::new(
+        ErrorCode::ReaderClosed,
+        "The selection reader is closed".to_string(),
+      ));
+    };
+    let result = selection::get_selection_text_with(backends, &self.options);
+    if let Err(err) = &result {
+      if matches!(
+        err.status,
+        ErrorCode::ClipboardUnavailable
+          | ErrorCode::KeyboardInitFailed
+          | ErrorCode::KeySimulationFailed
+      ) {
+        backends.reset();
+      }
+    }
+    result
+  }
+}
+
+/// Retrieves the selection text as `getSelectionText` does, with the options it is created with,
+/// but keeps the clipboard and keyboard connections open across reads instead of opening new ones
+/// each time, which makes each read faster. The connections are opened again if the display
+/// connection is lost.
+#[napi]
+pub struct SelectionReader {
+  state: Arc<Mutex<ReaderState>>,
+}
+
+#[napi]
+impl SelectionReader {
+  /// ##### Arguments
+  /// * `options` - Same as `getSelectionText`, used for every read
+  ///
+  /// ##### Errors
+  /// Throws an `INVALID_SHORTCUT` or `INVALID_APP_MATCHER` error if the options cannot be parsed
+  #[napi(constructor)]
+  pub fn new(options: Option<Either<u32, GetSelectionTextOptions>>) -> Result<Self> {
+    Ok(SelectionReader {
+      state: Arc::new(Mutex::new(ReaderState {
+        options: options::resolve_options(options)?,
+        backends: Some(SystemBackends::default()),
+      })),
+    })
+  }
+
+  /// Retrieves the current selection text, see `getSelectionText`.
+  ///
+  /// ##### Errors
+  /// Throws the same errors as `getSelectionText`, or a `READER_CLOSED` error if the reader is
+  /// closed
+  #[napi]
+  pub fn read(&self) -> Result<String> {
+    self.state().read()
+  }
+
+  /// Async version of `read`, see `getSelectionTextAsync`. Reads of the same reader run one at a
+  /// time.
+  #[napi(ts_return_type = "Promise<string>")]
+  pub fn read_async(&self) -> AsyncTask<ReadSelectionTextTask> {
+    AsyncTask::new(ReadSelectionTextTask {
+      state: self.state.clone(),
+    })
+  }
+
+  /// Closes the clipboard and keyboard connections. Later reads throw a `READER_CLOSED` error.
+  /// Calling it again does nothing.
+  #[napi]
+  pub fn close(&self) {
+    self.state().backends = None;
+  }
+
+  fn state(&self) -> MutexGuard<'_, ReaderState> {
+    lock(&self.state)
+  }
+}
+
+/// Locks the reader state, which stays usable even if a read panicked while holding it
+pub fn lock(state: &Mutex<ReaderState>) -> MutexGuard<'_, ReaderState> {
+  state.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fails_to_read_once_closed() {
+    let reader = SelectionReader::new(None).unwrap();
+    reader.close();
+    reader.close();
+    let err = reader.read().unwrap_err();
+    assert_eq!(err.status, ErrorCode::ReaderClosed);
+  }
+}
@@ -0,0 +1,335 @@
+use std::time::Duration;
+
+use crate::backend::{ClipboardBackend, KeystrokeBackend, SystemClipboard, SystemKeystroke};
+use crate::error::Result;
+#[cfg(target_os = "linux")]
+use crate::linux;
+use crate::lock;
+use crate::selection::{
+  self, ModifierOptions, ModifierPolicy, TerminalPolicy, DEFAULT_MODIFIER_TIMEOUT_MS,
+  DEFAULT_POLL_INTERVAL_MS,
+};
+use crate::shortcut::Shortcut;
+
+pub static DEFAULT_PASTE_WAIT_TIME_MS: u32 = 200;
+
+/// Options that control how the selection is replaced
+#[derive(Debug, Clone)]
+pub struct ReplaceOptions {
+  /// Maximum time to wait after the paste for the focused app to read the clipboard, which it does
+  /// asynchronously, before restoring the clipboard
+  pub paste_wait_time_ms: u32,
+  /// Whether to restore the clipboard previous content after the paste, or keep the pasted text
+  pub restore_clipboard: bool,
+  pub modifier_policy: ModifierPolicy,
+  /// Maximum time to wait for held modifier keys to be released with [`ModifierPolicy::Wait`]
+  pub modifier_timeout_ms: u32,
+  /// Shortcut that pastes the clipboard into the focused app
+  pub paste_shortcut: Shortcut,
+  /// Terminal emulators are pasted into with `Ctrl + Shift + V` unless it is
+  /// [`TerminalPolicy::Ignore`]
+  #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+  pub terminal_policy: TerminalPolicy,
+  /// Names of terminal emulators to detect in addition to the known ones
+  #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+  pub terminals: Vec<String>,
+  /// Whether to mark the pasted text and the restored clipboard content for clipboard managers to
+  /// not record them
+  pub exclude_from_history: bool,
+  /// Whether to fail before any keyboard input is simulated if the clipboard content is concealed
+  pub skip_if_concealed: bool,
+  /// Whether to fail right away if another call is using the clipboard, instead of waiting for it
+  pub fail_if_busy: bool,
+}
+
+impl ReplaceOptions {
+  fn modifier_options(&self) -> ModifierOptions {
+    ModifierOptions {
+      policy: self.modifier_policy,
+      timeout_ms: self.modifier_timeout_ms,
+      poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+    }
+  }
+}
+
+impl Default for ReplaceOptions {
+  fn default() -> Self {
+    Self {
+      paste_wait_time_ms: DEFAULT_PASTE_WAIT_TIME_MS,
+      restore_clipboard: true,
+      modifier_policy: ModifierPolicy::default(),
+      modifier_timeout_ms: DEFAULT_MODIFIER_TIMEOUT_MS,
+      paste_shortcut: Shortcut::paste(),
+      terminal_policy: TerminalPolicy::Shortcut,
+      terminals: Vec::new(),
+      exclude_from_history: true,
+      skip_if_concealed: false,
+      fail_if_busy: false,
+    }
+  }
+}
+
+/// Replaces the current selection with the text by pasting it, see
+/// [`crate::replace_selection_text`] for the full description of the process
+pub fn replace_selection_text(text: &str, options: &ReplaceOptions) -> Result<()> {
+  let _clipboard_lock = lock::acquire(options.fail_if_busy)?;
+  let mut clipboard = SystemClipboard::new()?;
+  let paste_shortcut = terminal_paste_shortcut(&clipboard, options);
+  let options = ReplaceOptions {
+    paste_shortcut,
+    ..options.clone()
+  };
+  paste_text(&mut clipboard, &mut SystemKeystroke::new()?, text, &options)
+}
+
+/// Returns the shortcut to paste into the focused window with, which is `Ctrl + Shift + V` if it
+/// is a terminal emulator, where `Ctrl + V` is sent to the running program instead
+#[cfg(target_os = "linux")]
+fn terminal_paste_shortcut(clipboard: &SystemClipboard, options: &ReplaceOptions) -> Shortcut {
+  let is_terminal = options.terminal_policy != TerminalPolicy::Ignore
+    && clipboard
+      .active_source()
+      .is_some_and(|source| linux::terminal::is_terminal(&source, &options.terminals));
+  if is_terminal {
+    Shortcut::terminal_paste()
+  } else {
+    options.paste_shortcut.clone()
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn terminal_paste_shortcut(_clipboard: &SystemClipboard, options: &ReplaceOptions) -> Shortcut {
+  options.paste_shortcut.clone()
+}
+
+/// Pastes the text into the focused app: the clipboard is saved, set to the text and restored once
+/// the paste shortcut is simulated and the focused app reads the text, or `paste_wait_time_ms` has
+/// elapsed. The clipboard is restored even if the paste fails
+pub fn paste_text<C: ClipboardBackend, K: KeystrokeBackend>(
+  clipboard: &mut C,
+  keyboard: &mut K,
+  text: &str,
+  options: &ReplaceOptions,
+) -> Result<()> {
+  // Save clipboard existing content in every format
+  let clipboard_snapshot = clipboard.capture()?;
+  if options.skip_if_concealed {
+    selection::refuse_concealed(clipboard, &clipboard_snapshot)?;
+  }
+
+  let paste_result = clipboard
+    .write_text(
+      text,
+      options.exclude_from_history,
+      options.restore_clipboard,
+    )
+    .and_then(|_| {
+      selection::simulate_shortcut(
+        keyboard,
+        &options.paste_shortcut,
+        &options.modifier_options(),
+      )
+    });
+  if paste_result.is_ok() {
+    // The focused app reads the clipboard asynchronously, once it handles the paste
+    clipboard.wait_for_read(Duration::from_millis(u64::from(options.paste_wait_time_ms)));
+  }
+
+  // A paste failure is reported over a restore failure, as it is the cause
+  let restore_result = if options.restore_clipboard || paste_result.is_err() {
+    clipboard.restore(clipboard_snapshot, options.exclude_from_history)
+  } else {
+    Ok(())
+  };
+  paste_result.and(restore_result)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{thread, time::Instant};
+
+  use super::*;
+  use crate::backend::fake::{FakeDesktop, TEXT_MIME_TYPE};
+  use crate::error::ErrorCode;
+  use crate::keycode;
+
+  fn text(text: &str) -> Vec<(String, String)> {
+    vec![(TEXT_MIME_TYPE.to_string(), text.to_string())]
+  }
+
+  fn paste_options() -> ReplaceOptions {
+    ReplaceOptions {
+      paste_wait_time_ms: 20,
+      ..Default::default()
+    }
+  }
+
+  fn paste(desktop: &FakeDesktop, replacement: &str, options: &ReplaceOptions) -> Result<()> {
+    paste_text(
+      &mut desktop.clipboard(),
+      &mut desktop.keyboard(),
+      replacement,
+      options,
+    )
+  }
+
+  #[test]
+  fn pastes_text_and_restores_clipboard() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+
+    paste(&desktop, "Replaced", &paste_options()).unwrap();
+
+    assert_eq!(desktop.selection_text().as_deref(), Some("Replaced"));
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+    assert_eq!(desktop.paste_count(), 1);
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn excludes_pasted_text_and_restored_clipboard_from_history() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+
+    paste(&desktop, "Replaced", &paste_options()).unwrap();
+    assert!(desktop.clipboard_history().is_empty());
+
+    let options = ReplaceOptions {
+      exclude_from_history: false,
+      ..paste_options()
+    };
+    paste(&desktop, "Replaced", &options).unwrap();
+    assert_eq!(desktop.clipboard_history(), vec!["Replaced", "Previous"]);
+  }
+
+  #[test]
+  fn keeps_pasted_text_if_restore_is_disabled() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    let options = ReplaceOptions {
+      restore_clipboard: false,
+      ..paste_options()
+    };
+
+    paste(&desktop, "Replaced", &options).unwrap();
+
+    assert_eq!(desktop.clipboard_text().as_deref(), Some("Replaced"));
+  }
+
+  #[test]
+  fn pastes_previous_clipboard_if_paste_outlasts_wait_time() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_paste_delay(Duration::from_millis(50));
+
+    paste(&desktop, "Replaced", &paste_options()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(desktop.selection_text().as_deref(), Some("Previous"));
+  }
+
+  #[test]
+  fn restores_clipboard_once_pasted_text_is_read() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_paste_delay(Duration::from_millis(20));
+    let options = ReplaceOptions {
+      paste_wait_time_ms: 2000,
+      ..Default::default()
+    };
+
+    let start = Instant::now();
+    paste(&desktop, "Replaced", &options).unwrap();
+
+    assert!(start.elapsed() < Duration::from_millis(1000));
+    assert_eq!(desktop.selection_text().as_deref(), Some("Replaced"));
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+  }
+
+  #[test]
+  fn releases_held_modifiers_around_paste() {
+    let desktop = FakeDesktop::new();
+    desktop.hold_keys(&[keycode::LEFT_ALT], None);
+
+    paste(&desktop, "Replaced", &paste_options()).unwrap();
+
+    assert_eq!(desktop.selection_text().as_deref(), Some("Replaced"));
+    assert_eq!(desktop.held_keys(), vec![keycode::LEFT_ALT]);
+  }
+
+  #[test]
+  fn pastes_with_shortcut_set_in_options() {
+    let desktop = FakeDesktop::new();
+    desktop.set_app_paste_shortcuts(vec!["Shift+Insert".parse().unwrap()]);
+
+    paste(&desktop, "Replaced", &paste_options()).unwrap();
+    assert_eq!(desktop.paste_count(), 0);
+
+    let options = ReplaceOptions {
+      paste_shortcut: "Shift+Insert".parse().unwrap(),
+      ..paste_options()
+    };
+    paste(&desktop, "Replaced", &options).unwrap();
+    assert_eq!(desktop.selection_text().as_deref(), Some("Replaced"));
+  }
+
+  #[test]
+  fn refuses_to_paste_over_concealed_clipboard() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_concealed_clipboard_text("Password");
+    let options = ReplaceOptions {
+      skip_if_concealed: true,
+      ..paste_options()
+    };
+
+    let err = paste(&desktop, "Replaced", &options).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::ClipboardConcealed);
+    assert_eq!(desktop.paste_count(), 0);
+    assert_eq!(desktop.selection_text().as_deref(), Some("Selected"));
+  }
+
+  #[test]
+  fn restores_clipboard_if_paste_fails() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_failing_keycode(Some(keycode::V));
+    let options = ReplaceOptions {
+      restore_clipboard: false,
+      ..paste_options()
+    };
+
+    let err = paste(&desktop, "Replaced", &options).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::KeySimulationFailed);
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn reports_paste_error_if_restore_fails_too() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_failing_keycode(Some(keycode::V));
+    desktop.set_failing_restore(true);
+
+    let err = paste(&desktop, "Replaced", &paste_options()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::KeySimulationFailed);
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn reports_restore_error_after_paste() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_failing_restore(true);
+
+    let err = paste(&desktop, "Replaced", &paste_options()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::ClipboardRestoreFailed);
+    assert_eq!(desktop.selection_text().as_deref(), Some("Replaced"));
+  }
+}
@@ -0,0 +1,49 @@
+use crate::selection::{Selection, SelectionStrategy};
+use crate::source::SelectionSource;
+
+/// The selection content returned by `getSelection`
+#[napi(object)]
+pub struct SelectionResult {
+  /// The selection text, which is an empty string if there is no selection
+  pub text: String,
+  /// The selection HTML, if the source offered it
+  pub html: Option<String>,
+  /// The selection RTF, if the source offered it
+  pub rtf: Option<String>,
+  /// Every format the source offered the selection in, e.g. MIME types in Linux, UTIs in Mac and
+  /// clipboard format names in Windows
+  pub offered_formats: Vec<String>,
+  /// The strategy that retrieved the selection
+  #[napi(ts_type = "'copy' | 'primary'")]
+  pub strategy: SelectionStrategy,
+  /// The time it took to retrieve the selection, in milliseconds
+  pub elapsed_ms: f64,
+  /// The application the selection was retrieved from, captured before any keyboard input is
+  /// simulated. It is available in Windows, Mac and Linux X11 sessions, but not in Linux Wayland
+  /// sessions, where the focused window is not exposed to other apps
+  pub source: Option<SelectionSource>,
+}
+
+impl SelectionResult {
+  pub fn new(selection: Selection, strategy: SelectionStrategy, elapsed_ms: f64) -> Self {
+    SelectionResult {
+      text: selection.text,
+      html: selection.formats.html,
+      rtf: selection.formats.rtf,
+      offered_formats: selection.formats.offered,
+      strategy,
+      elapsed_ms,
+      source: selection.source,
+    }
+  }
+}
+
+/// The clipboard content reported by `onClipboardChange`
+#[napi(object)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct ClipboardChange {
+  /// The clipboard text, which is `null` if the clipboard has no text, e.g. an image was copied
+  pub text: Option<String>,
+  /// Every format the clipboard content is offered in, e.g. MIME types in Linux
+  pub formats: Vec<String>,
+}
@@ -0,0 +1,944 @@
+use enigo::Direction::{Click, Press, Release};
+use std::{
+  borrow::Cow,
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::apps::AppRules;
+use crate::backend::{ClipboardBackend, DesktopBackend, KeystrokeBackend, SystemBackends};
+use crate::error::{Error, ErrorCode, Result};
+use crate::formats::{SelectionFormat, SelectionFormats};
+#[cfg(target_os = "linux")]
+use crate::linux;
+use crate::lock;
+use crate::shortcut::Shortcut;
+use crate::source::SelectionSource;
+
+pub static DEFAULT_COPY_WAIT_TIME_MS: u32 = 5;
+pub static DEFAULT_TIMEOUT_MS: u32 = 100;
+pub static DEFAULT_POLL_INTERVAL_MS: u32 = 5;
+pub static DEFAULT_MODIFIER_TIMEOUT_MS: u32 = 1000;
+/// Number of times the selection is copied again with [`FocusChangePolicy::Retry`]
+pub static FOCUS_CHANGE_RETRIES: u32 = 2;
+
+/// Strategy used to retrieve the selection text
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SelectionStrategy {
+  /// Copy the selection text to the clipboard by simulating `Ctrl + C` (`Cmd + C` in Mac)
+  Copy,
+  /// Read the primary selection, which holds the highlighted text in Linux (X11 or Wayland), without
+  /// simulating any keyboard input or touching the clipboard
+  Primary,
+}
+
+impl Default for SelectionStrategy {
+  /// Defaults to `Primary` in Linux when the primary selection is readable and `Copy` elsewhere
+  fn default() -> Self {
+    #[cfg(target_os = "linux")]
+    if linux::is_primary_selection_supported() {
+      return SelectionStrategy::Primary;
+    }
+    SelectionStrategy::Copy
+  }
+}
+
+/// What to do with modifier keys the user is still holding down (e.g. `Alt + Shift` of the global
+/// hotkey that triggered the call) when the copy shortcut is simulated, as they would turn it into
+/// a different shortcut
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq, Default)]
+pub enum ModifierPolicy {
+  /// Release them before the copy shortcut and press them back after it if the user is still
+  /// holding them down, see [`press_back_modifiers`]
+  #[default]
+  Release,
+  /// Wait until the user releases them, or release them as with `Release` if they are still held
+  /// down after the timeout
+  Wait,
+  /// Leave them as they are
+  Ignore,
+}
+
+/// What to do when the window that has the input focus is a terminal emulator, where `Ctrl + C`
+/// interrupts the running program instead of copying the selection (Linux X11 only)
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq, Default)]
+pub enum TerminalPolicy {
+  /// Read the primary selection instead of copying the selection
+  #[default]
+  Primary,
+  /// Copy the selection with `Ctrl + Shift + C` instead of the copy shortcuts
+  Shortcut,
+  /// Copy the selection as in any other window
+  Ignore,
+}
+
+/// What to do when the input focus moves to another window while the selection is being copied,
+/// e.g. the user switches windows right after triggering the copy, as the copied text could come
+/// from the wrong window (Linux X11 and Windows only)
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, PartialEq, Eq, Default)]
+pub enum FocusChangePolicy {
+  /// Fail with a `FOCUS_CHANGED` error
+  #[default]
+  Abort,
+  /// Copy the selection of the newly focused window instead, failing as with `Abort` if the focus
+  /// keeps moving
+  Retry,
+  /// Return the copied text anyway
+  Ignore,
+}
+
+/// Options that control how the modifier keys held down by the user are handled when a shortcut
+/// is simulated
+#[derive(Debug, Clone)]
+pub struct ModifierOptions {
+  pub policy: ModifierPolicy,
+  /// Maximum time to wait for held modifier keys to be released with [`ModifierPolicy::Wait`]
+  pub timeout_ms: u32,
+  /// Interval between checks of the held modifier keys with [`ModifierPolicy::Wait`]
+  pub poll_interval_ms: u32,
+}
+
+/// Options that control how the selection text is retrieved
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionOptions {
+  pub strategy: SelectionStrategy,
+  /// Minimum time to wait after the copy before reading the clipboard
+  pub copy_wait_time_ms: u32,
+  /// Maximum time to wait for the copied text to land on the clipboard, counted from the copy
+  pub timeout_ms: u32,
+  /// Interval between clipboard reads while waiting for the copied text
+  pub poll_interval_ms: u32,
+  /// Whether to restore the clipboard previous content after the copy, or keep the copied text
+  pub restore_clipboard: bool,
+  /// Maximum number of characters of the returned text
+  pub max_length: Option<u32>,
+  /// Rich text formats to read along with the text, only read by [`read_selection`]
+  pub formats: Vec<SelectionFormat>,
+  pub modifier_policy: ModifierPolicy,
+  /// Maximum time to wait for held modifier keys to be released with [`ModifierPolicy::Wait`]
+  pub modifier_timeout_ms: u32,
+  /// Shortcuts to try in order until one copies the selection
+  pub copy_shortcuts: Vec<Shortcut>,
+  pub terminal_policy: TerminalPolicy,
+  /// Names of terminal emulators to detect in addition to the known ones
+  pub terminals: Vec<String>,
+  pub focus_change_policy: FocusChangePolicy,
+  /// Whether to mark the restored clipboard content for clipboard managers to not record it
+  pub exclude_from_history: bool,
+  /// Whether to fail before any keyboard input is simulated if the clipboard content is concealed
+  pub skip_if_concealed: bool,
+  /// Applications to refuse retrieving the selection from, before any clipboard or keyboard input
+  pub app_rules: AppRules,
+  /// Whether to fail right away if another call is using the clipboard, instead of waiting for it
+  pub fail_if_busy: bool,
+}
+
+impl SelectionOptions {
+  pub fn modifier_options(&self) -> ModifierOptions {
+    ModifierOptions {
+      policy: self.modifier_policy,
+      timeout_ms: self.modifier_timeout_ms,
+      poll_interval_ms: self.poll_interval_ms,
+    }
+  }
+}
+
+impl Default for SelectionOptions {
+  fn default() -> Self {
+    Self {
+      strategy: SelectionStrategy::default(),
+      copy_wait_time_ms: DEFAULT_COPY_WAIT_TIME_MS,
+      timeout_ms: DEFAULT_TIMEOUT_MS,
+      poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+      restore_clipboard: true,
+      max_length: None,
+      formats: vec![SelectionFormat::Html, SelectionFormat::Rtf],
+      modifier_policy: ModifierPolicy::default(),
+      modifier_timeout_ms: DEFAULT_MODIFIER_TIMEOUT_MS,
+      copy_shortcuts: vec![Shortcut::copy()],
+      terminal_policy: TerminalPolicy::default(),
+      terminals: Vec::new(),
+      focus_change_policy: FocusChangePolicy::default(),
+      exclude_from_history: true,
+      skip_if_concealed: false,
+      app_rules: AppRules::default(),
+      fail_if_busy: false,
+    }
+  }
+}
+
+/// Selection content read in a single copy (or primary selection read)
+#[derive(Debug, Default)]
+pub struct Selection {
+  pub text: String,
+  pub formats: SelectionFormats,
+  /// Application the selection was retrieved from, only captured by [`read_selection`]
+  pub source: Option<SelectionSource>,
+}
+
+/// Retrieves the current selection text with the strategy set in options
+pub fn get_selection_text(options: &SelectionOptions) -> Result<String> {
+  get_selection_text_with(&mut SystemBackends::default(), options)
+}
+
+/// Same as [`get_selection_text`], run on the desktop, e.g. with the clipboard and keyboard input
+/// kept in [`SystemBackends`]
+pub fn get_selection_text_with<D: DesktopBackend>(
+  desktop: &mut D,
+  options: &SelectionOptions,
+) -> Result<String> {
+  Ok(get_selection(desktop, options, false)?.0.text)
+}
+
+/// Retrieves the current selection along with the formats it is offered in, its rich text formats
+/// set in options and the application it is retrieved from. Returns the strategy that retrieved it
+/// too, which differs from the one set in options if it was adjusted for a terminal emulator
+pub fn read_selection(options: &SelectionOptions) -> Result<(Selection, SelectionStrategy)> {
+  get_selection(&mut SystemBackends::default(), options, true)
+}
+
+/// Retrieves the selection on the desktop as [`read_selection`] does, reading only its text unless
+/// `read_details` is set
+pub fn get_selection<D: DesktopBackend>(
+  desktop: &mut D,
+  options: &SelectionOptions,
+  read_details: bool,
+) -> Result<(Selection, SelectionStrategy)> {
+  let _clipboard_lock = lock::acquire(options.fail_if_busy)?;
+  retry_on_focus_change(options, || {
+    get_selection_once(desktop, options, read_details)
+  })
+}
+
+fn get_selection_once<D: DesktopBackend>(
+  desktop: &mut D,
+  options: &SelectionOptions,
+  read_details: bool,
+) -> Result<(Selection, SelectionStrategy)> {
+  // Capture the focused window before any keyboard input is simulated, so a focus change during the
+  // copy does not mislabel the selection source
+  let detects_terminal = cfg!(target_os = "linux")
+    && options.strategy == SelectionStrategy::Copy
+    && options.terminal_policy != TerminalPolicy::Ignore;
+  let source = if read_details || detects_terminal || !options.app_rules.is_empty() {
+    desktop.active_source()
+  } else {
+    None
+  };
+  options.app_rules.check(source.as_ref())?;
+
+  let options = terminal_options(options, source.as_ref());
+  let options = options.as_ref();
+  let mut selection = match options.strategy {
+    SelectionStrategy::Copy => {
+      let (clipboard, keyboard) = desktop.backends()?;
+      copy_selection(clipboard, keyboard, options, read_details)?
+    }
+    SelectionStrategy::Primary => desktop.primary_selection(&options.formats, read_details)?,
+  };
+  if read_details {
+    selection.source = source;
+  }
+  Ok((selection.truncate(options.max_length), options.strategy))
+}
+
+/// Runs the attempt to retrieve the selection again while it fails because the input focus moved
+/// to another window, up to [`FOCUS_CHANGE_RETRIES`] times with [`FocusChangePolicy::Retry`]
+pub fn retry_on_focus_change<T>(
+  options: &SelectionOptions,
+  mut attempt: impl FnMut() -> Result<T>,
+) -> Result<T> {
+  let mut retries = match options.focus_change_policy {
+    FocusChangePolicy::Retry => FOCUS_CHANGE_RETRIES,
+    _ => 0,
+  };
+  loop {
+    match attempt() {
+      Err(err) if err.status == ErrorCode::FocusChanged && retries > 0 => retries -= 1,
+      result => return result,
+    }
+  }
+}
+
+/// Adjusts the options to not interrupt the running program if the selection is to be copied from
+/// a terminal emulator, according to the terminal policy
+#[cfg(target_os = "linux")]
+fn terminal_options<'a>(
+  options: &'a SelectionOptions,
+  source: Option<&SelectionSource>,
+) -> Cow<'a, SelectionOptions> {
+  let is_terminal =
+    source.is_some_and(|source| linux::terminal::is_terminal(source, &options.terminals));
+  if options.strategy != SelectionStrategy::Copy
+    || options.terminal_policy == TerminalPolicy::Ignore
+    || !is_terminal
+  {
+    return Cow::Borrowed(options);
+  }
+  Cow::Owned(for_terminal(options))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn terminal_options<'a>(
+  options: &'a SelectionOptions,
+  _source: Option<&SelectionSource>,
+) -> Cow<'a, SelectionOptions> {
+  Cow::Borrowed(options)
+}
+
+/// Returns the options to retrieve the selection from a terminal emulator with
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn for_terminal(options: &SelectionOptions) -> SelectionOptions {
+  let mut options = options.clone();
+  match options.terminal_policy {
+    TerminalPolicy::Primary => options.strategy = SelectionStrategy::Primary,
+    TerminalPolicy::Shortcut => options.copy_shortcuts = vec![Shortcut::terminal_copy()],
+    TerminalPolicy::Ignore => {}
+  }
+  options
+}
+
+impl Selection {
+  /// Truncates the text to at most `max_length` characters
+  pub fn truncate(mut self, max_length: Option<u32>) -> Self {
+    if let Some(max_length) = max_length {
+      self.text = truncate(self.text, max_length as usize);
+    }
+    self
+  }
+}
+
+/// Truncates the text to at most `max_length` characters
+pub fn truncate(mut text: String, max_length: usize) -> String {
+  if let Some((index, _)) = text.char_indices().nth(max_length) {
+    text.truncate(index);
+  }
+  text
+}
+
+/// Retrieves the current selection by copying it to the clipboard, see
+/// [`crate::get_selection_text`] for the full description of the process. The formats are read
+/// from the clipboard while the copied selection is on it, before the clipboard is restored
+pub fn copy_selection<C: ClipboardBackend, K: KeystrokeBackend>(
+  clipboard: &mut C,
+  keyboard: &mut K,
+  options: &SelectionOptions,
+  read_formats: bool,
+) -> Result<Selection> {
+  // Record the focused window before any keyboard input is simulated, to tell whether the copied
+  // text comes from it
+  let focused_window = match options.focus_change_policy {
+    FocusChangePolicy::Ignore => None,
+    _ => keyboard.focused_window(),
+  };
+
+  // Save clipboard existing content in every format
+  let clipboard_snapshot = clipboard.capture()?;
+  if options.skip_if_concealed {
+    refuse_concealed(clipboard, &clipboard_snapshot)?;
+  }
+
+  // Clear clipboard
+  clipboard.clear()?;
+
+  // Simulate Ctrl/Cmd + C keyboard input (or the copy shortcuts set in options, in order) to copy
+  // selection text to clipboard, and wait for clipboard to be updated with it
+  let mut copy_result = Ok(());
+  let mut selection_text = String::new();
+  for shortcut in &options.copy_shortcuts {
+    copy_result = simulate_shortcut(keyboard, shortcut, &options.modifier_options());
+    if copy_result.is_err() {
+      break;
+    }
+    selection_text = wait_for_clipboard_text(clipboard, options);
+    if focused_window.is_some() && keyboard.focused_window() != focused_window {
+      // The text may have been copied from another window, and the next shortcut would be sent to
+      // it, so the copy is aborted
+      selection_text.clear();
+      copy_result = Err(Error::new(
+        ErrorCode::FocusChanged,
+        "The input focus moved to another window during the copy".to_string(),
+      ));
+      break;
+    }
+    if !selection_text.is_empty() {
+      break;
+    }
+  }
+  let formats = if read_formats && !selection_text.is_empty() {
+    clipboard.read_formats(&options.formats)
+  } else {
+    SelectionFormats::default()
+  };
+
+  // Restore clipboard previous existing content (or clear it if it was empty) to minimize side
+  // effects to users. This is done even if the copy failed, so a failed call does not leave the
+  // clipboard cleared. The copied selection text is kept instead only if the caller asks for it.
+  // A copy failure is reported over a restore failure, as it is the cause
+  let restore_result = if options.restore_clipboard || selection_text.is_empty() {
+    clipboard.restore(clipboard_snapshot, options.exclude_from_history)
+  } else {
+    Ok(())
+  };
+
+  copy_result.and(restore_result)?;
+  Ok(Selection {
+    text: selection_text,
+    formats,
+    ..Default::default()
+  })
+}
+
+/// Fails with [`ErrorCode::ClipboardConcealed`] if the saved clipboard content is concealed, e.g. a
+/// password copied from a password manager
+pub fn refuse_concealed<C: ClipboardBackend>(clipboard: &C, snapshot: &C::Snapshot) -> Result<()> {
+  if clipboard.is_concealed(snapshot) {
+    return Err(Error::new(
+      ErrorCode::ClipboardConcealed,
+      "The clipboard holds concealed content, e.g. a password".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Polls the (previously cleared) clipboard until text lands on it or the timeout is reached, in
+/// which case the selection is considered empty. The first read happens after `copy_wait_time_ms`
+fn wait_for_clipboard_text<C: ClipboardBackend>(
+  clipboard: &mut C,
+  options: &SelectionOptions,
+) -> String {
+  let start = Instant::now();
+  let min_wait = Duration::from_millis(u64::from(options.copy_wait_time_ms));
+  let timeout = Duration::from_millis(u64::from(options.timeout_ms)).max(min_wait);
+  let poll_interval = Duration::from_millis(u64::from(options.poll_interval_ms.max(1)));
+
+  thread::sleep(min_wait);
+  loop {
+    if let Some(text) = clipboard.read_text() {
+      if !text.is_empty() {
+        return text;
+      }
+    }
+    let elapsed = start.elapsed();
+    if elapsed >= timeout {
+      return String::new();
+    }
+    thread::sleep(poll_interval.min(timeout - elapsed));
+  }
+}
+
+/// Simulates the shortcut (e.g. `Ctrl + C`) keyboard input with the physical keys, so it works
+/// with any keyboard layout. The modifier keys pressed are released even if clicking the key fails,
+/// so a failure never leaves them stuck down. Modifier keys held down by the user are handled
+/// according to the modifier policy, and the released ones are pressed back afterward, see
+/// [`press_back_modifiers`]
+pub fn simulate_shortcut<K: KeystrokeBackend>(
+  keyboard: &mut K,
+  shortcut: &Shortcut,
+  options: &ModifierOptions,
+) -> Result<()> {
+  let released_modifiers = release_held_modifiers(keyboard, options)?;
+  let mut pressed_modifiers = Vec::new();
+  let mut shortcut_result = shortcut
+    .modifiers
+    .iter()
+    .try_for_each(|modifier| {
+      keyboard.raw(*modifier, Press)?;
+      pressed_modifiers.push(*modifier);
+      Ok(())
+    })
+    .and_then(|_| keyboard.raw(shortcut.key, Click));
+  for modifier in pressed_modifiers.iter().rev() {
+    shortcut_result = shortcut_result.and(keyboard.raw(*modifier, Release));
+  }
+  let press_back_result = press_back_modifiers(keyboard, &released_modifiers);
+  shortcut_result.and(press_back_result)
+}
+
+/// Gets the modifier keys held down by the user out of the way of the shortcut (or typed text)
+/// according to the modifier policy. Returns the released ones, which the user is still holding
+/// down
+pub fn release_held_modifiers<K: KeystrokeBackend>(
+  keyboard: &mut K,
+  options: &ModifierOptions,
+) -> Result<Vec<u16>> {
+  match options.policy {
+    ModifierPolicy::Ignore => return Ok(Vec::new()),
+    ModifierPolicy::Wait => {
+      let deadline = Instant::now() + Duration::from_millis(u64::from(options.timeout_ms));
+      let poll_interval = Duration::from_millis(u64::from(options.poll_interval_ms.max(1)));
+      while !keyboard.held_modifiers()?.is_empty() && Instant::now() < deadline {
+        thread::sleep(poll_interval);
+      }
+    }
+    ModifierPolicy::Release => {}
+  }
+
+  let mut released_modifiers = Vec::new();
+  for modifier in keyboard.held_modifiers()? {
+    if let Err(err) = keyboard.raw(modifier, Release) {
+      // Do not leave the ones already released up while the user is holding them down
+      let _ = press_back_modifiers(keyboard, &released_modifiers);
+      return Err(err);
+    }
+    released_modifiers.push(modifier);
+  }
+  Ok(released_modifiers)
+}
+
+/// Presses back the released modifier keys the user is still physically holding down. If the
+/// physical key state cannot be told, none is pressed back, as the user may have let go of them in
+/// the meantime (e.g. during the copy wait), and a press without their matching release would leave
+/// the key stuck down
+pub fn press_back_modifiers<K: KeystrokeBackend>(
+  keyboard: &mut K,
+  released_modifiers: &[u16],
+) -> Result<()> {
+  let Some(held_modifiers) = keyboard.physically_held_modifiers() else {
+    return Ok(());
+  };
+  released_modifiers
+    .iter()
+    .filter(|modifier| held_modifiers.contains(modifier))
+    .try_for_each(|modifier| keyboard.raw(*modifier, Press))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+  use crate::apps::AppPattern;
+  use crate::backend::fake::{FakeDesktop, HTML_MIME_TYPE, TEXT_MIME_TYPE};
+  use crate::error::ErrorCode;
+  use crate::keycode;
+
+  fn copy_options() -> SelectionOptions {
+    SelectionOptions {
+      strategy: SelectionStrategy::Copy,
+      ..Default::default()
+    }
+  }
+
+  fn text(text: &str) -> Vec<(String, String)> {
+    vec![(TEXT_MIME_TYPE.to_string(), text.to_string())]
+  }
+
+  fn copy(desktop: &FakeDesktop, options: &SelectionOptions) -> Result<Selection> {
+    copy_selection(
+      &mut desktop.clipboard(),
+      &mut desktop.keyboard(),
+      options,
+      true,
+    )
+  }
+
+  #[test]
+  fn copies_selection_and_restores_clipboard() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+
+    let selection = copy(&desktop, &copy_options()).unwrap();
+
+    assert_eq!(selection.text, "Selected");
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+    assert_eq!(desktop.copy_count(), 1);
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn leaves_empty_clipboard_empty() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+
+    assert_eq!(copy(&desktop, &copy_options()).unwrap().text, "Selected");
+    assert!(desktop.clipboard_content().is_empty());
+  }
+
+  #[test]
+  fn keeps_copied_selection_if_restore_is_disabled() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+    let options = SelectionOptions {
+      restore_clipboard: false,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+    assert_eq!(desktop.clipboard_text().as_deref(), Some("Selected"));
+  }
+
+  #[test]
+  fn excludes_restored_clipboard_from_history() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+
+    copy(&desktop, &copy_options()).unwrap();
+    assert_eq!(desktop.clipboard_history(), vec!["Selected"]);
+
+    let options = SelectionOptions {
+      exclude_from_history: false,
+      ..copy_options()
+    };
+    copy(&desktop, &options).unwrap();
+    assert_eq!(
+      desktop.clipboard_history(),
+      vec!["Selected", "Selected", "Previous"]
+    );
+  }
+
+  #[test]
+  fn preserves_concealed_clipboard() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_concealed_clipboard_text("Password");
+    let concealed = desktop.clipboard_content();
+    let options = SelectionOptions {
+      exclude_from_history: false,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+    assert_eq!(desktop.clipboard_content(), concealed);
+    assert_eq!(desktop.clipboard_history(), vec!["Selected"]);
+  }
+
+  #[test]
+  fn refuses_to_copy_over_concealed_clipboard() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_concealed_clipboard_text("Password");
+    let concealed = desktop.clipboard_content();
+    let options = SelectionOptions {
+      skip_if_concealed: true,
+      ..copy_options()
+    };
+
+    let err = copy(&desktop, &options).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::ClipboardConcealed);
+    assert_eq!(desktop.copy_count(), 0);
+    assert_eq!(desktop.clipboard_content(), concealed);
+
+    desktop.set_clipboard(text("Previous"));
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+  }
+
+  #[test]
+  fn returns_empty_text_without_selection() {
+    let desktop = FakeDesktop::new();
+    desktop.set_clipboard(text("Previous"));
+    let options = SelectionOptions {
+      restore_clipboard: false,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "");
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+  }
+
+  #[test]
+  fn waits_for_slow_copy_until_timeout() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_copy_delay(Duration::from_millis(30));
+
+    let options = SelectionOptions {
+      timeout_ms: 500,
+      ..copy_options()
+    };
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+
+    let options = SelectionOptions {
+      timeout_ms: 10,
+      ..copy_options()
+    };
+    desktop.set_copy_delay(Duration::from_secs(10));
+    assert_eq!(copy(&desktop, &options).unwrap().text, "");
+  }
+
+  #[test]
+  fn reads_rich_formats_of_copied_selection() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection(vec![
+      (TEXT_MIME_TYPE.to_string(), "Bold".to_string()),
+      (HTML_MIME_TYPE.to_string(), "<b>Bold</b>".to_string()),
+    ]);
+
+    let selection = copy(&desktop, &copy_options()).unwrap();
+
+    assert_eq!(selection.formats.html.as_deref(), Some("<b>Bold</b>"));
+    assert_eq!(selection.formats.rtf, None);
+    assert_eq!(
+      selection.formats.offered,
+      vec![TEXT_MIME_TYPE.to_string(), HTML_MIME_TYPE.to_string()]
+    );
+  }
+
+  #[test]
+  fn releases_modifier_and_restores_clipboard_if_copy_fails() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_failing_keycode(Some(keycode::C));
+
+    let err = copy(&desktop, &copy_options()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::KeySimulationFailed);
+    assert!(desktop.held_keys().is_empty());
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+  }
+
+  #[test]
+  fn reports_copy_failure_over_restore_failure() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_failing_keycode(Some(keycode::C));
+    desktop.set_failing_restore(true);
+
+    let err = copy(&desktop, &copy_options()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::KeySimulationFailed);
+    assert!(desktop.held_keys().is_empty());
+
+    desktop.set_failing_keycode(None);
+    let err = copy(&desktop, &copy_options()).unwrap_err();
+    assert_eq!(err.status, ErrorCode::ClipboardRestoreFailed);
+  }
+
+  #[test]
+  fn releases_held_modifiers_and_presses_them_back() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.hold_keys(&[keycode::LEFT_ALT, keycode::LEFT_SHIFT], None);
+
+    assert_eq!(copy(&desktop, &copy_options()).unwrap().text, "Selected");
+    assert_eq!(
+      desktop.held_keys(),
+      vec![keycode::LEFT_ALT, keycode::LEFT_SHIFT]
+    );
+  }
+
+  #[test]
+  fn does_not_press_back_modifiers_released_during_copy() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_copy_delay(Duration::from_millis(60));
+    desktop.hold_keys(&[keycode::LEFT_ALT], Some(Duration::from_millis(20)));
+
+    assert_eq!(copy(&desktop, &copy_options()).unwrap().text, "Selected");
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn leaves_modifiers_released_without_physical_key_state() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_physical_key_state(false);
+    desktop.hold_keys(&[keycode::LEFT_ALT], None);
+
+    assert_eq!(copy(&desktop, &copy_options()).unwrap().text, "Selected");
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn ignores_held_modifiers() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.hold_keys(&[keycode::LEFT_ALT], None);
+    let options = SelectionOptions {
+      modifier_policy: ModifierPolicy::Ignore,
+      ..copy_options()
+    };
+
+    // The app gets `Ctrl + Alt + C` instead of the copy shortcut
+    assert_eq!(copy(&desktop, &options).unwrap().text, "");
+    assert_eq!(desktop.copy_count(), 0);
+  }
+
+  #[test]
+  fn waits_for_held_modifiers_to_be_released() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.hold_keys(&[keycode::LEFT_SHIFT], Some(Duration::from_millis(30)));
+    let options = SelectionOptions {
+      modifier_policy: ModifierPolicy::Wait,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn releases_modifiers_still_held_after_wait_timeout() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.hold_keys(&[keycode::LEFT_SHIFT], None);
+    let options = SelectionOptions {
+      modifier_policy: ModifierPolicy::Wait,
+      modifier_timeout_ms: 20,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+    assert_eq!(desktop.held_keys(), vec![keycode::LEFT_SHIFT]);
+  }
+
+  #[test]
+  fn tries_copy_shortcuts_in_order() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_app_copy_shortcuts(vec!["Ctrl+Shift+C".parse().unwrap()]);
+    let options = SelectionOptions {
+      copy_shortcuts: vec![
+        "Ctrl+Insert".parse().unwrap(),
+        "Ctrl+Shift+C".parse().unwrap(),
+      ],
+      timeout_ms: 20,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+    assert_eq!(desktop.copy_count(), 1);
+    assert!(desktop.held_keys().is_empty());
+  }
+
+  #[test]
+  fn aborts_if_focus_changes_during_copy() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_clipboard(text("Previous"));
+    desktop.set_focus_switches(vec![2]);
+
+    let err = copy(&desktop, &copy_options()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::FocusChanged);
+    assert_eq!(desktop.clipboard_content(), text("Previous"));
+  }
+
+  #[test]
+  fn retries_if_focus_changes_during_copy() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_focus_switches(vec![2, 3]);
+    let options = SelectionOptions {
+      focus_change_policy: FocusChangePolicy::Retry,
+      ..copy_options()
+    };
+
+    let selection = retry_on_focus_change(&options, || copy(&desktop, &options)).unwrap();
+    assert_eq!(selection.text, "Selected");
+    assert_eq!(desktop.copy_count(), 3);
+
+    desktop.set_focus_switches(vec![4, 5, 6]);
+    let err = retry_on_focus_change(&options, || copy(&desktop, &options)).unwrap_err();
+    assert_eq!(err.status, ErrorCode::FocusChanged);
+  }
+
+  #[test]
+  fn ignores_focus_change_during_copy() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Selected");
+    desktop.set_focus_switches(vec![2]);
+    let options = SelectionOptions {
+      focus_change_policy: FocusChangePolicy::Ignore,
+      ..copy_options()
+    };
+
+    assert_eq!(copy(&desktop, &options).unwrap().text, "Selected");
+  }
+
+  #[test]
+  fn avoids_copy_shortcut_in_terminals() {
+    let options = SelectionOptions {
+      copy_shortcuts: vec!["Ctrl+Insert".parse().unwrap()],
+      ..copy_options()
+    };
+    assert_eq!(for_terminal(&options).strategy, SelectionStrategy::Primary);
+
+    let options = SelectionOptions {
+      terminal_policy: TerminalPolicy::Shortcut,
+      ..options
+    };
+    let terminal_options = for_terminal(&options);
+    assert_eq!(terminal_options.strategy, SelectionStrategy::Copy);
+    assert_eq!(
+      terminal_options.copy_shortcuts,
+      vec!["Ctrl+Shift+C".parse().unwrap()]
+    );
+  }
+
+  #[test]
+  fn checks_focused_app_before_copying() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Password");
+    desktop.set_clipboard(text("Previous"));
+    let options = SelectionOptions {
+      app_rules: AppRules {
+        deny: vec![AppPattern {
+          class: Some("keepassxc".to_string()),
+          ..Default::default()
+        }],
+        ..Default::default()
+      },
+      ..copy_options()
+    };
+
+    for focused_app in [
+      Some(SelectionSource {
+        class: Some("KeePassXC".to_string()),
+        ..Default::default()
+      }),
+      None,
+    ] {
+      desktop.set_focused_app(focused_app);
+      let err = get_selection(&mut desktop.backends(), &options, false).unwrap_err();
+      assert_eq!(err.status, ErrorCode::AppDenied);
+    }
+    assert_eq!(desktop.copy_count(), 0);
+    assert_eq!(desktop.clipboard_text().as_deref(), Some("Previous"));
+
+    let gedit = SelectionSource {
+      class: Some("Gedit".to_string()),
+      ..Default::default()
+    };
+    desktop.set_focused_app(Some(gedit.clone()));
+    let (selection, strategy) = get_selection(&mut desktop.backends(), &options, true).unwrap();
+    assert_eq!(selection.text, "Password");
+    assert_eq!(selection.source, Some(gedit));
+    assert_eq!(strategy, SelectionStrategy::Copy);
+  }
+
+  #[test]
+  fn reads_primary_selection_without_copying() {
+    let desktop = FakeDesktop::new();
+    desktop.set_selection_text("Hello, 世界");
+    let options = SelectionOptions {
+      strategy: SelectionStrategy::Primary,
+      max_length: Some(8),
+      ..Default::default()
+    };
+
+    let text = get_selection_text_with(&mut desktop.backends(), &options).unwrap();
+    assert_eq!(text, "Hello, 世");
+    assert_eq!(desktop.copy_count(), 0);
+  }
+
+  #[test]
+  fn truncates_text_to_max_length() {
+    let selection = Selection {
+      text: "Hello, 世界".to_string(),
+      ..Default::default()
+    };
+    assert_eq!(selection.truncate(Some(8)).text, "Hello, 世");
+  }
+}
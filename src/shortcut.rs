@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorCode};
+use crate::keycode;
+
+/// Keyboard shortcut, as the physical keycodes of its modifier keys and of its key, see
+/// [`crate::keycode`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+  pub modifiers: Vec<u16>,
+  pub key: u16,
+}
+
+impl Shortcut {
+  /// `Ctrl + C` (`Cmd + C` in Mac)
+  pub fn copy() -> Self {
+    Shortcut {
+      modifiers: vec![keycode::COPY_MODIFIER],
+      key: keycode::C,
+    }
+  }
+
+  /// `Ctrl + Shift + C`, which copies the selection in terminal emulators
+  pub fn terminal_copy() -> Self {
+    Shortcut {
+      modifiers: vec![keycode::LEFT_CONTROL, keycode::LEFT_SHIFT],
+      key: keycode::C,
+    }
+  }
+
+  /// `Ctrl + V` (`Cmd + V` in Mac)
+  pub fn paste() -> Self {
+    Shortcut {
+      modifiers: vec![keycode::COPY_MODIFIER],
+      key: keycode::V,
+    }
+  }
+
+  /// `Ctrl + Shift + V`, which pastes the clipboard in terminal emulators
+  pub fn terminal_paste() -> Self {
+    Shortcut {
+      modifiers: vec![keycode::LEFT_CONTROL, keycode::LEFT_SHIFT],
+      key: keycode::V,
+    }
+  }
+}
+
+/// Parses a key-chord description, i.e. modifier keys followed by a key joined with `+`, e.g.
+/// `Ctrl+Shift+C` or `Ctrl+Insert`. Names are case insensitive:
+/// * Modifier keys - `Ctrl` (`Control`), `Shift`, `Alt` (`Option`), `Cmd` (`Command`, `Meta`,
+///   `Super`, `Win`) and `CmdOrCtrl` (`Cmd` in Mac and `Ctrl` elsewhere)
+/// * Keys - Letters, digits, `F1` to `F12`, `Insert`, `Delete`, `Enter`, `Tab`, `Space` and `Escape`
+impl FromStr for Shortcut {
+  type Err = Error;
+
+  fn from_str(description: &str) -> Result<Self, Error> {
+    let invalid = |message: String| {
+      Error::new(
+        ErrorCode::InvalidShortcut,
+        format!("Invalid shortcut \"{description}\": {message}"),
+      )
+    };
+    let mut names: Vec<&str> = description.split('+').map(str::trim).collect();
+    if names.iter().any(|name| name.is_empty()) {
+      return Err(invalid("Empty key name".to_string()));
+    }
+    let key_name = names.pop().unwrap_or_default();
+    if parse_modifier(key_name).is_some() {
+      return Err(invalid("Missing a key after the modifier keys".to_string()));
+    }
+    let key = parse_key(key_name).ok_or_else(|| invalid(format!("Unknown key \"{key_name}\"")))?;
+    let mut modifiers = Vec::new();
+    for name in names {
+      let modifier =
+        parse_modifier(name).ok_or_else(|| invalid(format!("Unknown modifier key \"{name}\"")))?;
+      if modifiers.contains(&modifier) {
+        return Err(invalid(format!("Duplicate modifier key \"{name}\"")));
+      }
+      modifiers.push(modifier);
+    }
+    Ok(Shortcut { modifiers, key })
+  }
+}
+
+fn parse_modifier(name: &str) -> Option<u16> {
+  match name.to_ascii_lowercase().as_str() {
+    "ctrl" | "control" => Some(keycode::LEFT_CONTROL),
+    "shift" => Some(keycode::LEFT_SHIFT),
+    "alt" | "option" => Some(keycode::LEFT_ALT),
+    "cmd" | "command" | "meta" | "super" | "win" => Some(keycode::LEFT_META),
+    "cmdorctrl" | "commandorcontrol" => Some(keycode::COPY_MODIFIER),
+    _ => None,
+  }
+}
+
+fn parse_key(name: &str) -> Option<u16> {
+  let name = name.to_ascii_lowercase();
+  if let [character] = name.as_bytes() {
+    return match character {
+      b'a'..=b'z' => Some(keycode::LETTERS[usize::from(character - b'a')]),
+      b'0'..=b'9' => Some(keycode::DIGITS[usize::from(character - b'0')]),
+      _ => None,
+    };
+  }
+  if let Some(number) = name
+    .strip_prefix('f')
+    .and_then(|number| number.parse::<usize>().ok())
+  {
+    return (1..=12)
+      .contains(&number)
+      .then(|| keycode::FUNCTION_KEYS[number - 1]);
+  }
+  match name.as_str() {
+    "insert" | "ins" => Some(keycode::INSERT),
+    "delete" | "del" => Some(keycode::DELETE),
+    "enter" | "return" => Some(keycode::ENTER),
+    "tab" => Some(keycode::TAB),
+    "space" => Some(keycode::SPACE),
+    "escape" | "esc" => Some(keycode::ESCAPE),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(description: &str) -> Shortcut {
+    description.parse().unwrap()
+  }
+
+  #[test]
+  fn parses_modifiers_and_key() {
+    assert_eq!(
+      parse("Ctrl+Shift+C"),
+      Shortcut {
+        modifiers: vec![keycode::LEFT_CONTROL, keycode::LEFT_SHIFT],
+        key: keycode::C,
+      }
+    );
+    assert_eq!(
+      parse("ctrl + insert"),
+      Shortcut {
+        modifiers: vec![keycode::LEFT_CONTROL],
+        key: keycode::INSERT,
+      }
+    );
+    assert_eq!(parse("CmdOrCtrl+C"), Shortcut::copy());
+    assert_eq!(parse("F12").key, keycode::FUNCTION_KEYS[11]);
+    assert_eq!(parse("Alt+0").key, keycode::DIGITS[0]);
+  }
+
+  #[test]
+  fn rejects_invalid_descriptions() {
+    for description in [
+      "",
+      "Ctrl+",
+      "Ctrl++C",
+      "Ctrl+Shift",
+      "Ctrl+Foo",
+      "Hyper+C",
+      "Ctrl+Control+C",
+      "F13",
+      "Ctrl+Ç",
+    ] {
+      let err = description.parse::<Shortcut>().unwrap_err();
+      assert_eq!(err.status, ErrorCode::InvalidShortcut, "{description}");
+    }
+  }
+}
@@ -0,0 +1,78 @@
+use arboard::{Clipboard, ImageData};
+use std::path::PathBuf;
+
+use crate::error::{ErrorCode, Result, WithErrorCode};
+use crate::zeroize;
+
+/// Snapshot of the clipboard content in the formats arboard supports, i.e. text, HTML, image and
+/// file list. It is used where raw formats cannot be read and served, e.g. X11 content too large
+/// to be read at once. The text, HTML and image are zeroized when dropped
+#[derive(Default)]
+pub struct ClipboardSnapshot {
+  text: Option<String>,
+  html: Option<String>,
+  image: Option<ImageData<'static>>,
+  file_list: Option<Vec<PathBuf>>,
+  concealed: bool,
+}
+
+impl ClipboardSnapshot {
+  /// Reads the content through the clipboard. It is concealed if `has_concealment_marker`, i.e.
+  /// the owner offers the marker of a password manager along with it, which arboard cannot read
+  pub fn capture(clipboard: &mut Clipboard, has_concealment_marker: bool) -> Result<Self> {
+    Ok(Self {
+      text: clipboard.get_text().ok().filter(|text| !text.is_empty()),
+      html: clipboard.get().html().ok(),
+      image: clipboard.get_image().ok(),
+      file_list: clipboard.get().file_list().ok(),
+      concealed: has_concealment_marker,
+    })
+  }
+
+  /// Whether the content was marked concealed by the app that set it, e.g. a password manager
+  pub fn is_concealed(&self) -> bool {
+    self.concealed
+  }
+
+  fn is_empty(&self) -> bool {
+    self.text.is_none() && self.html.is_none() && self.image.is_none() && self.file_list.is_none()
+  }
+
+  /// Writes back the richest captured format, since arboard can only set one format at a time
+  /// (except HTML, which is set along with its text alternative). Only concealed content is marked
+  /// as excluded from history, which is the concealment marker in Linux
+  pub fn restore(&self, clipboard: &mut Clipboard) -> Result<()> {
+    if self.is_empty() {
+      return clipboard
+        .clear()
+        .with_code(ErrorCode::ClipboardRestoreFailed);
+    }
+    let set = clipboard.set();
+    let set = if self.concealed {
+      super::exclude_from_history(set)
+    } else {
+      set
+    };
+    let result = if let Some(file_list) = &self.file_list {
+      set.file_list(file_list)
+    } else if let Some(image) = &self.image {
+      set.image(image.clone())
+    } else if let Some(html) = &self.html {
+      set.html(html.as_str(), self.text.as_deref())
+    } else {
+      set.text(self.text.as_deref().unwrap_or_default())
+    };
+    result.with_code(ErrorCode::ClipboardRestoreFailed)
+  }
+}
+
+impl Drop for ClipboardSnapshot {
+  fn drop(&mut self) {
+    for text in [&mut self.text, &mut self.html].into_iter().flatten() {
+      zeroize::string(text);
+    }
+    if let Some(image) = &mut self.image {
+      zeroize::vec(image.bytes.to_mut());
+    }
+  }
+}
@@ -0,0 +1,171 @@
+use arboard::Clipboard;
+use std::io::Read;
+use wl_clipboard_rs::{
+  copy::{self, MimeSource, Source},
+  paste::{self, ClipboardType, Error as PasteError, Seat},
+};
+
+use super::generic;
+use crate::{
+  error::{Error, ErrorCode, Result, WithErrorCode},
+  linux::{
+    self,
+    x11::{self, X11Connection},
+  },
+  zeroize,
+};
+
+/// MIME type KDE Klipper, CopyQ and GPaste check to not record the clipboard content when set to
+/// `secret`, which password managers (e.g. KeePassXC) set along with a copied password
+pub const PASSWORD_MANAGER_HINT_MIME_TYPE: &str = "x-kde-passwordManagerHint";
+
+/// Snapshot of the clipboard content. In Wayland, every MIME type is read through the
+/// data-control protocol and served again on restore. In X11, every target is read and served
+/// again the same way. Elsewhere (compositors without the protocol, or X11 content too large to be
+/// read at once), it falls back to the formats arboard supports. The data is zeroized when dropped
+pub enum ClipboardSnapshot {
+  Wayland {
+    mime_types: Vec<(String, Vec<u8>)>,
+    concealed: bool,
+  },
+  X11 {
+    targets: Vec<x11::SelectionTarget>,
+    concealed: bool,
+  },
+  Generic(generic::ClipboardSnapshot),
+}
+
+impl ClipboardSnapshot {
+  /// Reads the content through the connection to the X server in X11 sessions (where `x11` is
+  /// set), through the data-control protocol in Wayland sessions, or else through the clipboard
+  pub fn capture(clipboard: &mut Clipboard, x11: Option<&X11Connection>) -> Result<Self> {
+    if linux::is_wayland_session() {
+      match capture_wayland() {
+        Ok(mime_types) => {
+          let concealed = is_concealed(
+            mime_types
+              .iter()
+              .map(|(mime_type, data)| (mime_type.as_str(), data.as_slice())),
+          );
+          return Ok(Self::Wayland {
+            mime_types,
+            concealed,
+          });
+        }
+        Err(PasteError::MissingProtocol { .. } | PasteError::WaylandConnection(_)) => {}
+        Err(err) => return Err(err).with_code(ErrorCode::ClipboardUnavailable),
+      }
+    } else if let Some(x11) = x11 {
+      if let Some(targets) = x11.read_all_targets("CLIPBOARD")? {
+        let concealed = is_concealed(x11_content(&targets));
+        return Ok(Self::X11 { targets, concealed });
+      }
+    }
+    generic::ClipboardSnapshot::capture(clipboard, has_concealment_marker(x11)).map(Self::Generic)
+  }
+
+  /// Whether the content was marked concealed by the app that set it, e.g. a password manager
+  pub fn is_concealed(&self) -> bool {
+    match self {
+      Self::Wayland { concealed, .. } | Self::X11 { concealed, .. } => *concealed,
+      Self::Generic(snapshot) => snapshot.is_concealed(),
+    }
+  }
+
+  /// Writes the content back as it was, with the concealment marker only if it had it. Unlike in
+  /// Windows and Mac, the history exclusion marker is the one password managers conceal passwords
+  /// with, so the content is not marked as a secret it is not, and clipboard managers may record it
+  pub fn restore(&self, clipboard: &mut Clipboard) -> Result<()> {
+    match self {
+      Self::Wayland { mime_types, .. } if mime_types.is_empty() => {
+        copy::clear(copy::ClipboardType::Regular, copy::Seat::All)
+          .with_code(ErrorCode::ClipboardRestoreFailed)
+      }
+      Self::Wayland { mime_types, .. } => {
+        // The concealment marker is restored along with the other MIME types. The data is copied
+        // to the serving thread, where it cannot be zeroized
+        let sources: Vec<_> = mime_types
+          .iter()
+          .map(|(mime_type, data)| MimeSource {
+            source: Source::Bytes(data.clone().into_boxed_slice()),
+            mime_type: copy::MimeType::Specific(mime_type.clone()),
+          })
+          .collect();
+        let mut options = copy::Options::new();
+        options.omit_additional_text_mime_types(true);
+        options
+          .copy_multi(sources)
+          .with_code(ErrorCode::ClipboardRestoreFailed)
+      }
+      Self::X11 { targets, .. } if targets.is_empty() => {
+        x11::clear_selection("CLIPBOARD").map_err(restore_failed)
+      }
+      // The concealment marker is restored along with the other targets
+      Self::X11 { targets, .. } => x11::serve_selection("CLIPBOARD", targets.clone())
+        .map(|_| ())
+        .map_err(restore_failed),
+      Self::Generic(snapshot) => snapshot.restore(clipboard),
+    }
+  }
+}
+
+impl Drop for ClipboardSnapshot {
+  fn drop(&mut self) {
+    if let Self::Wayland { mime_types, .. } = self {
+      for (_, data) in mime_types {
+        zeroize::vec(data);
+      }
+    }
+  }
+}
+
+/// Returns whether the content, as (MIME type or target, data) pairs, has the concealment marker
+fn is_concealed<'a>(mut content: impl Iterator<Item = (&'a str, &'a [u8])>) -> bool {
+  content.any(|(mime_type, data)| mime_type == PASSWORD_MANAGER_HINT_MIME_TYPE && data == b"secret")
+}
+
+/// Returns whether the clipboard owner offers the `x-kde-passwordManagerHint` target set to
+/// `secret`, which password managers (e.g. KeePassXC) set along with a copied password
+fn has_concealment_marker(x11: Option<&X11Connection>) -> bool {
+  x11.is_some_and(|x11| {
+    x11
+      .read_selection("CLIPBOARD", PASSWORD_MANAGER_HINT_MIME_TYPE)
+      .ok()
+      .flatten()
+      .is_some_and(|data| data == b"secret")
+  })
+}
+
+fn x11_content(targets: &[x11::SelectionTarget]) -> impl Iterator<Item = (&str, &[u8])> {
+  targets
+    .iter()
+    .map(|target| (target.name.as_str(), target.data.as_slice()))
+}
+
+fn restore_failed(err: Error) -> Error {
+  Error::new(ErrorCode::ClipboardRestoreFailed, err.reason)
+}
+
+/// Reads the clipboard content of every offered MIME type, in the order the source offers them
+fn capture_wayland() -> std::result::Result<Vec<(String, Vec<u8>)>, PasteError> {
+  let mime_types = match paste::get_mime_types_ordered(ClipboardType::Regular, Seat::Unspecified) {
+    Ok(mime_types) => mime_types,
+    Err(PasteError::ClipboardEmpty | PasteError::NoMimeType) => return Ok(Vec::new()),
+    Err(err) => return Err(err),
+  };
+  let mut snapshot = Vec::with_capacity(mime_types.len());
+  for mime_type in mime_types {
+    let (mut pipe, _) = paste::get_contents(
+      ClipboardType::Regular,
+      Seat::Unspecified,
+      paste::MimeType::Specific(&mime_type),
+    )?;
+    let mut data = Vec::new();
+    if pipe.read_to_end(&mut data).is_ok() {
+      snapshot.push((mime_type, data));
+    } else {
+      zeroize::vec(&mut data);
+    }
+  }
+  Ok(snapshot)
+}
@@ -0,0 +1,127 @@
+use objc2::{rc::Retained, runtime::ProtocolObject};
+use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardWriting};
+use objc2_foundation::{NSArray, NSData, NSString};
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::zeroize;
+
+/// Types clipboard managers (e.g. Maccy, Paste, Alfred) check to not record the pasteboard content,
+/// see http://nspasteboard.org
+const HISTORY_EXCLUSION_TYPES: [&str; 2] = [
+  "org.nspasteboard.TransientType",
+  "org.nspasteboard.ConcealedType",
+];
+
+/// Type password managers (e.g. 1Password, KeePassXC) set along with a copied password
+const CONCEALED_TYPE: &str = "org.nspasteboard.ConcealedType";
+
+/// Snapshot of the pasteboard content, i.e. the data of every type of every pasteboard item. The
+/// data is zeroized when dropped
+pub struct ClipboardSnapshot {
+  items: Vec<Vec<(String, Vec<u8>)>>,
+  concealed: bool,
+}
+
+impl ClipboardSnapshot {
+  pub fn capture() -> Result<Self> {
+    let pasteboard = NSPasteboard::generalPasteboard();
+    let items: Vec<Vec<_>> = pasteboard
+      .pasteboardItems()
+      .map(|items| {
+        items
+          .iter()
+          .map(|item| {
+            item
+              .types()
+              .iter()
+              .filter_map(|data_type| {
+                item
+                  .dataForType(&data_type)
+                  .map(|data| (data_type.to_string(), data.to_vec()))
+              })
+              .collect()
+          })
+          .collect()
+      })
+      .unwrap_or_default();
+    let has_marker = items
+      .iter()
+      .flatten()
+      .any(|(data_type, _)| data_type == CONCEALED_TYPE);
+    let mut snapshot = Self {
+      items,
+      concealed: false,
+    };
+    snapshot.concealed = has_marker && !super::is_excluded_by_us(snapshot.fingerprint());
+    Ok(snapshot)
+  }
+
+  /// Whether the content was marked concealed by the app that set it, e.g. a password manager
+  pub fn is_concealed(&self) -> bool {
+    self.concealed
+  }
+
+  fn is_empty(&self) -> bool {
+    self.items.iter().all(|item| item.is_empty())
+  }
+
+  /// Fingerprint of the content, leaving the history exclusion and concealment markers out
+  fn fingerprint(&self) -> u64 {
+    super::fingerprint(self.items.iter().map(|types| {
+      types
+        .iter()
+        .filter(|(data_type, _)| !HISTORY_EXCLUSION_TYPES.contains(&data_type.as_str()))
+        .collect::<Vec<_>>()
+    }))
+  }
+
+  pub fn restore(&self, exclude_from_history: bool) -> Result<()> {
+    let pasteboard = NSPasteboard::generalPasteboard();
+    pasteboard.clearContents();
+    if self.is_empty() {
+      return Ok(());
+    }
+    // The concealment markers are restored along with the other types
+    let items: Vec<Retained<ProtocolObject<dyn NSPasteboardWriting>>> = self
+      .items
+      .iter()
+      .enumerate()
+      .map(|(index, types)| {
+        let item = NSPasteboardItem::new();
+        for (data_type, data) in types {
+          item.setData_forType(&NSData::with_bytes(data), &NSString::from_str(data_type));
+        }
+        // Clipboard managers check the types of the first item
+        if exclude_from_history && index == 0 {
+          for data_type in HISTORY_EXCLUSION_TYPES {
+            if !types
+              .iter()
+              .any(|(captured_type, _)| captured_type == data_type)
+            {
+              item.setData_forType(&NSData::new(), &NSString::from_str(data_type));
+            }
+          }
+        }
+        ProtocolObject::from_retained(item)
+      })
+      .collect();
+    if !pasteboard.writeObjects(&NSArray::from_retained_slice(&items)) {
+      return Err(Error::new(
+        ErrorCode::ClipboardRestoreFailed,
+        "Failed to write items to the pasteboard".to_string(),
+      ));
+    }
+    if exclude_from_history && !self.concealed {
+      super::remember_excluded_by_us(self.fingerprint());
+    }
+    Ok(())
+  }
+}
+
+impl Drop for ClipboardSnapshot {
+  fn drop(&mut self) {
+    for (_, data) in self.items.iter_mut().flatten() {
+      zeroize::vec(data);
+    }
+  }
+}
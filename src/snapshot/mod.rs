@@ -0,0 +1,93 @@
+//! Snapshot of the clipboard content in every format it is offered in, so the content can be
+//! written back exactly as it was after the clipboard is used to copy the selection text.
+//!
+//! Each platform has its own implementation with the same interface, except that in Linux (and
+//! with the generic fallback) it is given the clipboard (and X server connection) the system
+//! clipboard keeps to read and write through, instead of connecting for each snapshot:
+//! * `ClipboardSnapshot::capture()` - Reads the clipboard content in every format
+//! * `ClipboardSnapshot::is_concealed()` - Whether the content was marked concealed by the app that
+//!   set it, e.g. a password manager. The markers are written back on restore
+//! * `ClipboardSnapshot::restore(exclude_from_history)` - Writes the captured content back to the
+//!   clipboard, or clears the clipboard if it had no content. With `exclude_from_history`, the
+//!   content is marked with the hints clipboard managers check to not record it:
+//!   * Windows: `ExcludeClipboardContentFromMonitorProcessing`, `CanIncludeInClipboardHistory` and
+//!     `CanUploadToCloudClipboard` formats
+//!   * Mac: `org.nspasteboard.TransientType` and `org.nspasteboard.ConcealedType` types
+//!
+//!   In Linux, the only hint is the `x-kde-passwordManagerHint` MIME type set to `secret`, which
+//!   password managers conceal passwords with, so the content is written back without it (and
+//!   without `exclude_from_history`) unless it had it
+
+#[cfg(any(windows, target_os = "macos"))]
+use std::{
+  hash::{DefaultHasher, Hash, Hasher},
+  sync::Mutex,
+};
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod generic;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub use generic::ClipboardSnapshot;
+#[cfg(target_os = "linux")]
+pub use linux::{ClipboardSnapshot, PASSWORD_MANAGER_HINT_MIME_TYPE};
+#[cfg(target_os = "macos")]
+pub use macos::ClipboardSnapshot;
+#[cfg(windows)]
+pub use windows::ClipboardSnapshot;
+
+/// Fingerprint of the content last restored with the history exclusion markers added by this
+/// process, so they are not taken for the concealment markers of a password manager later, as some
+/// are the same (e.g. `org.nspasteboard.ConcealedType` in Mac)
+#[cfg(any(windows, target_os = "macos"))]
+static EXCLUDED_BY_US: Mutex<Option<u64>> = Mutex::new(None);
+
+#[cfg(any(windows, target_os = "macos"))]
+fn fingerprint<T: Hash>(content: impl IntoIterator<Item = T>) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  for item in content {
+    item.hash(&mut hasher);
+  }
+  hasher.finish()
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn remember_excluded_by_us(fingerprint: u64) {
+  *EXCLUDED_BY_US.lock().unwrap_or_else(|err| err.into_inner()) = Some(fingerprint);
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn is_excluded_by_us(fingerprint: u64) -> bool {
+  *EXCLUDED_BY_US.lock().unwrap_or_else(|err| err.into_inner()) == Some(fingerprint)
+}
+
+/// Marks the content set through arboard for clipboard managers to not record it, with the same
+/// hints as the snapshot restore (only `org.nspasteboard.ConcealedType` in Mac)
+#[cfg(windows)]
+pub fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+  use arboard::SetExtWindows;
+  set.exclude_from_monitoring()
+}
+
+#[cfg(target_os = "macos")]
+pub fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+  use arboard::SetExtApple;
+  set.exclude_from_history()
+}
+
+#[cfg(target_os = "linux")]
+pub fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+  use arboard::SetExtLinux;
+  set.exclude_from_history()
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub fn exclude_from_history(set: arboard::Set<'_>) -> arboard::Set<'_> {
+  set
+}
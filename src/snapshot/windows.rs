@@ -0,0 +1,137 @@
+use clipboard_win::{raw, Clipboard};
+
+use crate::error::{ErrorCode, Result, WithErrorCode};
+use crate::zeroize;
+
+/// Number of attempts to open the clipboard, which fails while another app has it open
+const OPEN_ATTEMPTS: usize = 10;
+
+/// Formats clipboard managers and the Windows clipboard history check to not record the clipboard
+/// content, all set to a zero `DWORD`
+const HISTORY_EXCLUSION_FORMATS: [&str; 3] = [
+  "ExcludeClipboardContentFromMonitorProcessing",
+  "CanIncludeInClipboardHistory",
+  "CanUploadToCloudClipboard",
+];
+
+/// Formats password managers (e.g. KeePass, KeePassXC) set along with a copied password
+const CONCEALMENT_FORMATS: [&str; 2] = [
+  "ExcludeClipboardContentFromMonitorProcessing",
+  "Clipboard Viewer Ignore",
+];
+
+/// Snapshot of the clipboard content in every format, as raw bytes of each format global memory.
+/// The bytes are zeroized when dropped
+pub struct ClipboardSnapshot {
+  formats: Vec<(u32, Vec<u8>)>,
+  concealed: bool,
+}
+
+/// Returns whether the format data is a GDI handle (e.g. `CF_BITMAP`) or owner display data
+/// instead of global memory, so it cannot be copied as bytes. The images are still preserved, as
+/// Windows offers them as `CF_DIB`/`CF_DIBV5` too
+fn is_handle_format(format: u32) -> bool {
+  const CF_BITMAP: u32 = 2;
+  const CF_METAFILEPICT: u32 = 3;
+  const CF_PALETTE: u32 = 9;
+  const CF_ENHMETAFILE: u32 = 14;
+  const CF_OWNERDISPLAY: u32 = 0x80;
+  const CF_DSPBITMAP: u32 = 0x82;
+  const CF_DSPMETAFILEPICT: u32 = 0x83;
+  const CF_DSPENHMETAFILE: u32 = 0x8E;
+  const CF_GDIOBJFIRST: u32 = 0x300;
+  const CF_GDIOBJLAST: u32 = 0x3FF;
+  matches!(
+    format,
+    CF_BITMAP
+      | CF_METAFILEPICT
+      | CF_PALETTE
+      | CF_ENHMETAFILE
+      | CF_OWNERDISPLAY
+      | CF_DSPBITMAP
+      | CF_DSPMETAFILEPICT
+      | CF_DSPENHMETAFILE
+      | CF_GDIOBJFIRST..=CF_GDIOBJLAST
+  )
+}
+
+fn register_formats(names: &[&str]) -> Vec<u32> {
+  names
+    .iter()
+    .filter_map(|name| raw::register_format(name))
+    .map(|format| format.get())
+    .collect()
+}
+
+impl ClipboardSnapshot {
+  pub fn capture() -> Result<Self> {
+    let _clipboard =
+      Clipboard::new_attempts(OPEN_ATTEMPTS).with_code(ErrorCode::ClipboardUnavailable)?;
+    let formats: Vec<_> = raw::EnumFormats::new()
+      .filter(|format| !is_handle_format(*format))
+      .filter_map(|format| {
+        let mut data = Vec::new();
+        raw::get_vec(format, &mut data).ok().map(|_| (format, data))
+      })
+      .collect();
+    let concealment_formats = register_formats(&CONCEALMENT_FORMATS);
+    let has_marker = formats
+      .iter()
+      .any(|(format, _)| concealment_formats.contains(format));
+    let mut snapshot = Self {
+      formats,
+      concealed: false,
+    };
+    snapshot.concealed = has_marker && !super::is_excluded_by_us(snapshot.fingerprint());
+    Ok(snapshot)
+  }
+
+  /// Whether the content was marked concealed by the app that set it, e.g. a password manager
+  pub fn is_concealed(&self) -> bool {
+    self.concealed
+  }
+
+  /// Fingerprint of the content, leaving the history exclusion and concealment markers out
+  fn fingerprint(&self) -> u64 {
+    let markers = register_formats(&HISTORY_EXCLUSION_FORMATS)
+      .into_iter()
+      .chain(register_formats(&CONCEALMENT_FORMATS))
+      .collect::<Vec<_>>();
+    super::fingerprint(
+      self
+        .formats
+        .iter()
+        .filter(|(format, _)| !markers.contains(format)),
+    )
+  }
+
+  pub fn restore(&self, exclude_from_history: bool) -> Result<()> {
+    let _clipboard =
+      Clipboard::new_attempts(OPEN_ATTEMPTS).with_code(ErrorCode::ClipboardRestoreFailed)?;
+    raw::empty().with_code(ErrorCode::ClipboardRestoreFailed)?;
+    // The concealment markers are restored along with the other formats
+    for (format, data) in &self.formats {
+      raw::set_without_clear(*format, data).with_code(ErrorCode::ClipboardRestoreFailed)?;
+    }
+    if exclude_from_history && !self.formats.is_empty() {
+      for format in register_formats(&HISTORY_EXCLUSION_FORMATS) {
+        if !self.formats.iter().any(|(captured, _)| *captured == format) {
+          raw::set_without_clear(format, &0u32.to_ne_bytes())
+            .with_code(ErrorCode::ClipboardRestoreFailed)?;
+        }
+      }
+      if !self.concealed {
+        super::remember_excluded_by_us(self.fingerprint());
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Drop for ClipboardSnapshot {
+  fn drop(&mut self) {
+    for (_, data) in &mut self.formats {
+      zeroize::vec(data);
+    }
+  }
+}
@@ -0,0 +1,103 @@
+/// The application the selection was retrieved from, i.e. the one whose window had the input focus
+/// right before the selection was retrieved
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionSource {
+  /// The window title. It is not told in Mac, where it needs the screen recording permission
+  pub title: Option<String>,
+  /// The window class, e.g. `WM_CLASS` class name in Linux X11 (`"Gnome-terminal"`), the window
+  /// class name in Windows (`"CASCADIA_HOSTING_WINDOW_CLASS"`) or the app bundle identifier in Mac
+  /// (`"com.apple.Terminal"`)
+  pub class: Option<String>,
+  /// The window instance name, e.g. `WM_CLASS` instance name in Linux X11
+  /// (`"gnome-terminal-server"`) or the app name in Mac (`"Terminal"`)
+  pub instance: Option<String>,
+  /// The id of the process that owns the window
+  pub pid: Option<u32>,
+  /// The path of the executable of the process that owns the window
+  pub exe_path: Option<String>,
+}
+
+/// Returns the application whose window has the input focus, or `None` if it cannot be told. It is
+/// told in Windows and Mac. In Linux, it is told in X11 sessions (through EWMH) by
+/// [`crate::backend::SystemClipboard::active_source`], through the connection to the X server the
+/// clipboard keeps, but not in Wayland sessions, as Wayland does not expose the focused window to
+/// other clients
+#[cfg(target_os = "windows")]
+pub fn active_source() -> Option<SelectionSource> {
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+  };
+
+  let window = unsafe { GetForegroundWindow() };
+  if window.is_null() {
+    return None;
+  }
+  let mut buffer = [0u16; 512];
+  let length = unsafe { GetWindowTextW(window, buffer.as_mut_ptr(), buffer.len() as i32) };
+  let title = (length > 0).then(|| String::from_utf16_lossy(&buffer[..length as usize]));
+  let length = unsafe { GetClassNameW(window, buffer.as_mut_ptr(), buffer.len() as i32) };
+  let class = (length > 0).then(|| String::from_utf16_lossy(&buffer[..length as usize]));
+  let mut pid = 0;
+  unsafe { GetWindowThreadProcessId(window, &mut pid) };
+  let pid = (pid != 0).then_some(pid);
+  Some(SelectionSource {
+    title,
+    class,
+    instance: None,
+    pid,
+    exe_path: pid.and_then(process_exe_path),
+  })
+}
+
+/// Returns the path of the executable of the process, which only needs the limited query access
+/// right, so it is also told for most elevated processes
+#[cfg(target_os = "windows")]
+fn process_exe_path(pid: u32) -> Option<String> {
+  use windows_sys::Win32::{
+    Foundation::CloseHandle,
+    System::Threading::{
+      OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+      PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+  };
+
+  let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+  if process.is_null() {
+    return None;
+  }
+  let mut buffer = [0u16; 1024];
+  let mut length = buffer.len() as u32;
+  let succeeded = unsafe {
+    QueryFullProcessImageNameW(
+      process,
+      PROCESS_NAME_WIN32,
+      buffer.as_mut_ptr(),
+      &mut length,
+    )
+  };
+  unsafe { CloseHandle(process) };
+  (succeeded != 0).then(|| String::from_utf16_lossy(&buffer[..length as usize]))
+}
+
+#[cfg(target_os = "macos")]
+pub fn active_source() -> Option<SelectionSource> {
+  use objc2_app_kit::NSWorkspace;
+
+  let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+  Some(SelectionSource {
+    title: None,
+    class: app.bundleIdentifier().map(|id| id.to_string()),
+    instance: app.localizedName().map(|name| name.to_string()),
+    pid: u32::try_from(app.processIdentifier()).ok(),
+    exe_path: app
+      .executableURL()
+      .and_then(|url| url.path())
+      .map(|path| path.to_string()),
+  })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn active_source() -> Option<SelectionSource> {
+  None
+}
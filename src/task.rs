@@ -0,0 +1,73 @@
+use napi::{Env, Task};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+  error, lock,
+  reader::{self, ReaderState},
+  selection::{self, SelectionOptions},
+  typing::{self, TypeOptions, TypingTicket},
+};
+
+/// Runs [`selection::get_selection_text`] on the libuv thread pool, so the JS thread is not blocked
+/// while waiting for the copy to complete. Overlapping calls with the same options share a single
+/// copy, see [`lock::share_read`]
+pub struct GetSelectionTextTask {
+  /// Resolved options, or the error resolving them, which rejects the promise
+  pub options: error::Result<SelectionOptions>,
+}
+
+impl Task for GetSelectionTextTask {
+  type Output = error::Result<String>;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(match &self.options {
+      Ok(options) => lock::share_read(options, || selection::get_selection_text(options)),
+      Err(err) => Err(error::Error::new(err.status, err.reason.clone())),
+    })
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|err| error::into_napi_error(env, err))
+  }
+}
+
+/// Runs [`ReaderState::read`] on the libuv thread pool for `SelectionReader.readAsync`
+pub struct ReadSelectionTextTask {
+  pub state: Arc<Mutex<ReaderState>>,
+}
+
+impl Task for ReadSelectionTextTask {
+  type Output = error::Result<String>;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(reader::lock(&self.state).read())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|err| error::into_napi_error(env, err))
+  }
+}
+
+/// Runs [`typing::type_text`] on the libuv thread pool, so the JS thread is not blocked (and can
+/// cancel it) while the text is typed
+pub struct TypeTextTask {
+  pub text: String,
+  pub options: TypeOptions,
+  /// Taken when `typeText` is called, so `cancelTyping` also cancels the typing not begun yet
+  pub ticket: TypingTicket,
+}
+
+impl Task for TypeTextTask {
+  type Output = error::Result<()>;
+  type JsValue = ();
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(typing::type_text(&self.text, &self.options, &self.ticket))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|err| error::into_napi_error(env, err))
+  }
+}
@@ -0,0 +1,228 @@
+use std::{
+  sync::atomic::{AtomicU64, Ordering},
+  thread,
+  time::Duration,
+};
+
+use crate::backend::{KeystrokeBackend, SystemKeystroke};
+use crate::error::{Error, ErrorCode, Result};
+use crate::selection::{
+  self, ModifierOptions, ModifierPolicy, DEFAULT_MODIFIER_TIMEOUT_MS, DEFAULT_POLL_INTERVAL_MS,
+};
+
+pub static DEFAULT_CHUNK_SIZE: u32 = 50;
+
+/// Incremented by [`cancel_typing`], so the typing started before stops
+static TYPING_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Options that control how the text is typed
+#[derive(Debug, Clone)]
+pub struct TypeOptions {
+  /// Time to wait between chunks
+  pub delay_ms: u32,
+  /// Maximum number of characters typed at once
+  pub chunk_size: u32,
+  pub modifier_policy: ModifierPolicy,
+  /// Maximum time to wait for held modifier keys to be released with [`ModifierPolicy::Wait`]
+  pub modifier_timeout_ms: u32,
+}
+
+impl TypeOptions {
+  fn modifier_options(&self) -> ModifierOptions {
+    ModifierOptions {
+      policy: self.modifier_policy,
+      timeout_ms: self.modifier_timeout_ms,
+      poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+    }
+  }
+}
+
+impl Default for TypeOptions {
+  fn default() -> Self {
+    Self {
+      delay_ms: 0,
+      chunk_size: DEFAULT_CHUNK_SIZE,
+      modifier_policy: ModifierPolicy::default(),
+      modifier_timeout_ms: DEFAULT_MODIFIER_TIMEOUT_MS,
+    }
+  }
+}
+
+/// Identifies the typing started now, to tell whether [`cancel_typing`] was called since
+pub struct TypingTicket(u64);
+
+impl TypingTicket {
+  pub fn new() -> Self {
+    TypingTicket(TYPING_GENERATION.load(Ordering::SeqCst))
+  }
+
+  fn is_cancelled(&self) -> bool {
+    TYPING_GENERATION.load(Ordering::SeqCst) != self.0
+  }
+}
+
+/// Stops the typing in progress (and the typing started but not begun yet), before its next chunk
+pub fn cancel_typing() {
+  TYPING_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Types the text at the cursor of the focused app, see [`crate::type_text`] for the full
+/// description of the process
+pub fn type_text(text: &str, options: &TypeOptions, ticket: &TypingTicket) -> Result<()> {
+  type_text_with(&mut SystemKeystroke::new()?, text, options, || {
+    ticket.is_cancelled()
+  })
+}
+
+/// Types the text in chunks of `chunk_size` characters, waiting `delay_ms` between them, until
+/// cancelled. Modifier keys held down by the user are handled according to the modifier policy,
+/// as they would turn the typed characters into shortcuts
+pub fn type_text_with<K: KeystrokeBackend>(
+  keyboard: &mut K,
+  text: &str,
+  options: &TypeOptions,
+  is_cancelled: impl Fn() -> bool,
+) -> Result<()> {
+  let released_modifiers =
+    selection::release_held_modifiers(keyboard, &options.modifier_options())?;
+  let typing_result = type_chunks(keyboard, text, options, is_cancelled);
+  let press_back_result = selection::press_back_modifiers(keyboard, &released_modifiers);
+  typing_result.and(press_back_result)
+}
+
+fn type_chunks<K: KeystrokeBackend>(
+  keyboard: &mut K,
+  text: &str,
+  options: &TypeOptions,
+  is_cancelled: impl Fn() -> bool,
+) -> Result<()> {
+  let delay = Duration::from_millis(u64::from(options.delay_ms));
+  for (index, chunk) in chunks(text, options.chunk_size.max(1) as usize).enumerate() {
+    if index > 0 {
+      thread::sleep(delay);
+    }
+    if is_cancelled() {
+      return Err(Error::new(
+        ErrorCode::Cancelled,
+        "The typing was cancelled".to_string(),
+      ));
+    }
+    // A failed chunk is not typed again, as some of its characters may have been typed already
+    keyboard.text(chunk)?;
+  }
+  Ok(())
+}
+
+/// Splits the text into chunks of at most `chunk_size` characters
+fn chunks(text: &str, chunk_size: usize) -> impl Iterator<Item = &str> {
+  let mut rest = text;
+  std::iter::from_fn(move || {
+    if rest.is_empty() {
+      return None;
+    }
+    let end = rest
+      .char_indices()
+      .nth(chunk_size)
+      .map_or(rest.len(), |(index, _)| index);
+    let (chunk, tail) = rest.split_at(end);
+    rest = tail;
+    Some(chunk)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+
+  use super::*;
+  use crate::backend::fake::FakeDesktop;
+  use crate::keycode;
+
+  fn type_with(desktop: &FakeDesktop, text: &str, options: &TypeOptions) -> Result<()> {
+    type_text_with(&mut desktop.keyboard(), text, options, || false)
+  }
+
+  #[test]
+  fn splits_text_into_chunks() {
+    assert_eq!(
+      chunks("Hello, 世界", 4).collect::<Vec<_>>(),
+      ["Hell", "o, 世", "界"]
+    );
+    assert_eq!(chunks("", 4).count(), 0);
+  }
+
+  #[test]
+  fn types_text_in_chunks() {
+    let desktop = FakeDesktop::new();
+    let options = TypeOptions {
+      chunk_size: 4,
+      ..Default::default()
+    };
+
+    type_with(&desktop, "Hello, world", &options).unwrap();
+
+    assert_eq!(desktop.typed_text(), "Hello, world");
+    assert_eq!(desktop.typed_chunks(), 3);
+  }
+
+  #[test]
+  fn types_characters_outside_the_layout_with_the_chunk() {
+    let desktop = FakeDesktop::new();
+
+    type_with(&desktop, "Grüße, 世界", &TypeOptions::default()).unwrap();
+
+    assert_eq!(desktop.typed_text(), "Grüße, 世界");
+    assert_eq!(desktop.typed_chunks(), 1);
+  }
+
+  #[test]
+  fn does_not_type_failed_chunk_again() {
+    let desktop = FakeDesktop::new();
+    desktop.set_typing_failure_after(Some(3));
+
+    let err = type_with(&desktop, "Hello", &TypeOptions::default()).unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::KeySimulationFailed);
+    assert_eq!(desktop.typed_text(), "Hel");
+    assert_eq!(desktop.typed_chunks(), 1);
+  }
+
+  #[test]
+  fn releases_held_modifiers_while_typing() {
+    let desktop = FakeDesktop::new();
+    desktop.hold_keys(&[keycode::LEFT_ALT], None);
+
+    type_with(&desktop, "Hello", &TypeOptions::default()).unwrap();
+
+    assert_eq!(desktop.typed_text(), "Hello");
+    assert_eq!(desktop.held_keys(), vec![keycode::LEFT_ALT]);
+  }
+
+  #[test]
+  fn stops_typing_when_cancelled() {
+    let desktop = FakeDesktop::new();
+    let options = TypeOptions {
+      chunk_size: 2,
+      ..Default::default()
+    };
+    let checks = Cell::new(0);
+
+    let err = type_text_with(&mut desktop.keyboard(), "Hello", &options, || {
+      checks.set(checks.get() + 1);
+      checks.get() > 2
+    })
+    .unwrap_err();
+
+    assert_eq!(err.status, ErrorCode::Cancelled);
+    assert_eq!(desktop.typed_text(), "Hell");
+  }
+
+  #[test]
+  fn cancels_typing_started_before() {
+    let ticket = TypingTicket::new();
+    assert!(!ticket.is_cancelled());
+    cancel_typing();
+    assert!(ticket.is_cancelled());
+    assert!(!TypingTicket::new().is_cancelled());
+  }
+}
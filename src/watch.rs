@@ -0,0 +1,436 @@
+#[cfg(target_os = "linux")]
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::JsFunction;
+#[cfg(target_os = "linux")]
+use napi::{bindgen_prelude::ToNapiValue, JsError, JsUnknown, NapiValue};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::AtomicUsize;
+use std::{
+  collections::VecDeque,
+  hash::{DefaultHasher, Hash, Hasher},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+#[cfg(target_os = "linux")]
+use crate::error::WithErrorCode;
+use crate::error::{Error, ErrorCode, Result};
+#[cfg(target_os = "linux")]
+use crate::linux;
+use crate::options::WatchSelectionOptions;
+use crate::result::ClipboardChange;
+use crate::selection;
+
+#[cfg(target_os = "linux")]
+pub static DEFAULT_DEBOUNCE_MS: u32 = 150;
+
+/// How often the selection owner changes are checked for
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long the clipboard must stay unchanged before it is reported, as some apps set the
+/// clipboard content more than once per copy
+#[cfg(target_os = "linux")]
+const CLIPBOARD_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Number of the last clipboard contents this process set that are remembered, which covers the
+/// clear, copied selection and restore of a few calls in a row
+const OWN_CLIPBOARD_CONTENTS_KEPT: usize = 8;
+
+/// How long a clipboard content this process set is remembered, which the listeners read it well
+/// within, so the same content copied by the user later is reported
+const OWN_CLIPBOARD_CONTENT_EXPIRY: Duration = Duration::from_secs(2);
+
+/// Fingerprints of the last clipboard contents this process set (with when they were set), e.g.
+/// the clear, copied selection and restore of `getSelectionText`, so `onClipboardChange` does not
+/// report them
+static OWN_CLIPBOARD_CONTENTS: Mutex<VecDeque<(u64, Instant)>> = Mutex::new(VecDeque::new());
+
+/// Number of `onClipboardChange` listeners running, as the clipboard contents this process sets
+/// are only fingerprinted while one runs
+#[cfg(target_os = "linux")]
+static CLIPBOARD_LISTENERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Remembers the clipboard content as set by this process, right after it sets it, so
+/// `onClipboardChange` does not report it. It is read as the listeners read it, and nothing is
+/// read if no listener runs
+#[cfg(target_os = "linux")]
+pub fn remember_own_clipboard_content(
+  clipboard: &mut arboard::Clipboard,
+  x11: Option<&linux::x11::X11Connection>,
+) {
+  if has_clipboard_listeners() {
+    remember_own_clipboard_change(&read_clipboard_change(clipboard, x11));
+  }
+}
+
+/// Returns whether an `onClipboardChange` listener runs, which reads every clipboard content set
+#[cfg(target_os = "linux")]
+pub fn has_clipboard_listeners() -> bool {
+  CLIPBOARD_LISTENERS.load(Ordering::SeqCst) > 0
+}
+
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn remember_own_clipboard_change(change: &ClipboardChange) {
+  let mut contents = OWN_CLIPBOARD_CONTENTS
+    .lock()
+    .unwrap_or_else(|err| err.into_inner());
+  if contents.len() == OWN_CLIPBOARD_CONTENTS_KEPT {
+    contents.pop_front();
+  }
+  contents.push_back((fingerprint(change), Instant::now()));
+}
+
+/// Returns whether the clipboard content is one this process set, and forgets it along with the
+/// contents set before it, which the listeners read past, so the same content copied by the user
+/// later is reported
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn take_own_clipboard_change(change: &ClipboardChange) -> bool {
+  let mut contents = OWN_CLIPBOARD_CONTENTS
+    .lock()
+    .unwrap_or_else(|err| err.into_inner());
+  contents.retain(|(_, set_at)| set_at.elapsed() < OWN_CLIPBOARD_CONTENT_EXPIRY);
+  let fingerprint = fingerprint(change);
+  match contents.iter().position(|(own, _)| *own == fingerprint) {
+    Some(index) => {
+      contents.drain(..=index);
+      true
+    }
+    None => false,
+  }
+}
+
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn fingerprint(change: &ClipboardChange) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  change.text.hash(&mut hasher);
+  change.formats.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Counts a running `onClipboardChange` listener while alive
+#[cfg(target_os = "linux")]
+struct ClipboardListenerCount(());
+
+#[cfg(target_os = "linux")]
+impl ClipboardListenerCount {
+  fn start() -> Self {
+    CLIPBOARD_LISTENERS.fetch_add(1, Ordering::SeqCst);
+    ClipboardListenerCount(())
+  }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for ClipboardListenerCount {
+  fn drop(&mut self) {
+    CLIPBOARD_LISTENERS.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+/// Handle of the watcher started by `watchSelection` or `onClipboardChange`
+#[napi]
+pub struct SelectionWatcher {
+  stopped: Arc<AtomicBool>,
+}
+
+#[napi]
+impl SelectionWatcher {
+  /// Stops watching the selection, which lets the process exit. Calling it again does nothing.
+  #[napi]
+  pub fn stop(&self) {
+    self.stopped.store(true, Ordering::SeqCst);
+  }
+}
+
+/// Starts watching the primary selection on a background thread, calling the callback with its
+/// text each time it changes
+#[cfg(target_os = "linux")]
+pub fn watch_selection(
+  callback: JsFunction,
+  options: WatchSelectionOptions,
+) -> Result<SelectionWatcher> {
+  if !linux::is_x11_session() {
+    return Err(Error::new(
+      ErrorCode::StrategyUnsupported,
+      "Watching the selection is only available in Linux X11 sessions".to_string(),
+    ));
+  }
+  let listener = linux::x11::SelectionOwnerListener::new("PRIMARY")?;
+  let mut clipboard = arboard::Clipboard::new().with_code(ErrorCode::ClipboardUnavailable)?;
+  let debounce = Duration::from_millis(u64::from(
+    options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS),
+  ));
+  spawn_watcher(
+    callback,
+    debounce,
+    move || listener.poll(),
+    new_text_reader(options.max_length, move || {
+      linux::x11::get_primary_selection_text(&mut clipboard)
+    }),
+  )
+}
+
+/// Starts listening to the clipboard changes on a background thread, calling the callback with
+/// the new clipboard content each time it changes, except for the changes this process causes
+#[cfg(target_os = "linux")]
+pub fn on_clipboard_change(callback: JsFunction) -> Result<SelectionWatcher> {
+  let listener = linux::ClipboardListener::new()?;
+  let mut clipboard = arboard::Clipboard::new().with_code(ErrorCode::ClipboardUnavailable)?;
+  let x11 = linux::connect_x11()?;
+  let listener_count = ClipboardListenerCount::start();
+  spawn_watcher(
+    callback,
+    CLIPBOARD_DEBOUNCE,
+    move || listener.poll(),
+    move || {
+      // The listener is counted until it stops, when the closure is dropped
+      let _listener_count = &listener_count;
+      let change = read_clipboard_change(&mut clipboard, x11.as_ref());
+      Ok((!take_own_clipboard_change(&change)).then_some(change))
+    },
+  )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn on_clipboard_change(_callback: JsFunction) -> Result<SelectionWatcher> {
+  Err(Error::new(
+    ErrorCode::StrategyUnsupported,
+    "Listening to the clipboard changes is only available in Linux X11 and Wayland sessions"
+      .to_string(),
+  ))
+}
+
+#[cfg(target_os = "linux")]
+fn read_clipboard_change(
+  clipboard: &mut arboard::Clipboard,
+  x11: Option<&linux::x11::X11Connection>,
+) -> ClipboardChange {
+  ClipboardChange {
+    text: clipboard.get_text().ok(),
+    formats: crate::formats::read_clipboard_formats(&[], x11).offered,
+  }
+}
+
+/// Runs [`watch`] on a background thread, reporting to the callback
+#[cfg(target_os = "linux")]
+fn spawn_watcher<T: ToNapiValue + Send + 'static>(
+  callback: JsFunction,
+  debounce: Duration,
+  poll_changed: impl FnMut() -> Result<bool> + Send + 'static,
+  read: impl FnMut() -> Result<Option<T>> + Send + 'static,
+) -> Result<SelectionWatcher> {
+  let callback: ThreadsafeFunction<Result<T>, ErrorStrategy::Fatal> = callback
+    .create_threadsafe_function(0, callback_args)
+    .with_code(ErrorCode::ClipboardUnavailable)?;
+  let stopped = Arc::new(AtomicBool::new(false));
+  let watcher = SelectionWatcher {
+    stopped: stopped.clone(),
+  };
+  thread::spawn(move || {
+    watch(&stopped, debounce, poll_changed, read, |value| {
+      callback.call(value, ThreadsafeFunctionCallMode::NonBlocking);
+    })
+  });
+  Ok(watcher)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch_selection(
+  _callback: JsFunction,
+  _options: WatchSelectionOptions,
+) -> Result<SelectionWatcher> {
+  Err(Error::new(
+    ErrorCode::StrategyUnsupported,
+    "Watching the selection is only available in Linux X11 sessions".to_string(),
+  ))
+}
+
+/// Turns the reported value (or error) into the Node-style `(err, value)` callback arguments,
+/// keeping the error code
+#[cfg(target_os = "linux")]
+fn callback_args<T: ToNapiValue>(
+  ctx: ThreadSafeCallContext<Result<T>>,
+) -> napi::Result<Vec<JsUnknown>> {
+  Ok(match ctx.value {
+    Ok(value) => {
+      let value = unsafe { T::to_napi_value(ctx.env.raw(), value)? };
+      vec![ctx.env.get_null()?.into_unknown(), unsafe {
+        JsUnknown::from_raw_unchecked(ctx.env.raw(), value)
+      }]
+    }
+    Err(err) => vec![JsError::from(err).into_unknown(ctx.env)],
+  })
+}
+
+/// Wraps the selection text reading to skip empty text and text equal to the last reported one,
+/// and to truncate it to `max_length` characters
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn new_text_reader(
+  max_length: Option<u32>,
+  mut read_text: impl FnMut() -> Result<String>,
+) -> impl FnMut() -> Result<Option<String>> {
+  let mut last_text = String::new();
+  move || {
+    let text = read_text()?;
+    let text = match max_length {
+      Some(max_length) => selection::truncate(text, max_length as usize),
+      None => text,
+    };
+    if text.is_empty() || text == last_text {
+      return Ok(None);
+    }
+    last_text.clone_from(&text);
+    Ok(Some(text))
+  }
+}
+
+/// Reports the value read each time a change is polled, once no other change is polled for the
+/// debounce time, until stopped. Nothing is reported if `read` returns `None`. A failure to read is
+/// reported and watching goes on, while a failure to poll the changes is reported and stops
+/// watching
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn watch<T>(
+  stopped: &AtomicBool,
+  debounce: Duration,
+  mut poll_changed: impl FnMut() -> Result<bool>,
+  mut read: impl FnMut() -> Result<Option<T>>,
+  mut report: impl FnMut(Result<T>),
+) {
+  let mut read_at = None;
+  while !stopped.load(Ordering::SeqCst) {
+    match poll_changed() {
+      Ok(true) => read_at = Some(Instant::now() + debounce),
+      Ok(false) => {}
+      Err(err) => {
+        report(Err(err));
+        return;
+      }
+    }
+    if read_at.is_some_and(|read_at| Instant::now() >= read_at) {
+      read_at = None;
+      match read() {
+        Ok(Some(value)) if !stopped.load(Ordering::SeqCst) => report(Ok(value)),
+        Ok(_) => {}
+        Err(err) => report(Err(err)),
+      }
+    }
+    thread::sleep(POLL_INTERVAL);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::VecDeque;
+
+  use super::*;
+
+  #[test]
+  fn reports_debounced_selection_changes() {
+    let stopped = AtomicBool::new(false);
+    // Each poll tells whether the selection changed since the previous one
+    let mut changes: VecDeque<bool> = [true, true, false, false, false, false, false]
+      .into_iter()
+      .chain([true, false, false, false, false, false])
+      .chain([true, false, false, false, false, false])
+      .collect();
+    let mut texts = VecDeque::from(["Selected", "Selected", "Selected again"]);
+    let mut reported = Vec::new();
+
+    watch(
+      &stopped,
+      Duration::from_millis(20),
+      || {
+        let changed = changes.pop_front();
+        if changed.is_none() {
+          stopped.store(true, Ordering::SeqCst);
+        }
+        Ok(changed.unwrap_or(false))
+      },
+      new_text_reader(None, || {
+        Ok(texts.pop_front().unwrap_or_default().to_string())
+      }),
+      |text| reported.push(text.unwrap()),
+    );
+
+    assert_eq!(reported, vec!["Selected", "Selected again"]);
+    assert!(texts.is_empty());
+  }
+
+  #[test]
+  fn stops_watching_if_polling_fails() {
+    let stopped = AtomicBool::new(false);
+    let mut reported = Vec::new();
+
+    watch(
+      &stopped,
+      Duration::ZERO,
+      || {
+        Err(Error::new(
+          ErrorCode::ClipboardUnavailable,
+          "Connection lost".to_string(),
+        ))
+      },
+      || Ok(Some(())),
+      |text| reported.push(text.unwrap_err().status),
+    );
+
+    assert_eq!(reported, vec![ErrorCode::ClipboardUnavailable]);
+  }
+
+  /// Serializes the tests of the clipboard contents this process set, which are global
+  static OWN_CLIPBOARD_CONTENTS_TEST: Mutex<()> = Mutex::new(());
+
+  fn text_change(text: &str) -> ClipboardChange {
+    ClipboardChange {
+      text: Some(text.to_string()),
+      formats: vec!["text/plain".to_string()],
+    }
+  }
+
+  #[test]
+  fn tells_own_clipboard_changes() {
+    let _test = OWN_CLIPBOARD_CONTENTS_TEST.lock();
+    remember_own_clipboard_change(&text_change("Restored"));
+    assert!(!take_own_clipboard_change(&text_change(
+      "Copied by the user"
+    )));
+    assert!(!take_own_clipboard_change(&ClipboardChange {
+      formats: vec!["text/plain".to_string(), "text/html".to_string()],
+      ..text_change("Restored")
+    }));
+    assert!(take_own_clipboard_change(&text_change("Restored")));
+    // It is forgotten once told
+    assert!(!take_own_clipboard_change(&text_change("Restored")));
+
+    // Only the last contents are remembered
+    remember_own_clipboard_change(&text_change("Restored"));
+    for index in 0..OWN_CLIPBOARD_CONTENTS_KEPT {
+      remember_own_clipboard_change(&text_change(&format!("Set {index}")));
+    }
+    assert!(!take_own_clipboard_change(&text_change("Restored")));
+    assert!(take_own_clipboard_change(&text_change("Set 0")));
+  }
+
+  #[test]
+  fn reports_own_content_copied_again_by_the_user() {
+    let _test = OWN_CLIPBOARD_CONTENTS_TEST.lock();
+    // `getSelectionText` clears the clipboard, copies the selection and restores the clipboard,
+    // and the listener only reads the restored content once the clipboard settles
+    remember_own_clipboard_change(&ClipboardChange {
+      text: None,
+      formats: Vec::new(),
+    });
+    remember_own_clipboard_change(&text_change("Selected"));
+    remember_own_clipboard_change(&text_change("Copied before"));
+    assert!(take_own_clipboard_change(&text_change("Copied before")));
+
+    // The user copies the selection, then the restored content, again
+    assert!(!take_own_clipboard_change(&text_change("Selected")));
+    assert!(!take_own_clipboard_change(&text_change("Copied before")));
+  }
+}
@@ -0,0 +1,41 @@
+//! Overwriting of the clipboard content saved in memory once it is no longer needed, so a secret
+//! (e.g. a password copied from a password manager) does not linger in the process memory after
+//! the clipboard is restored
+
+use std::{
+  ptr,
+  sync::atomic::{compiler_fence, Ordering},
+};
+
+/// Overwrites the bytes with zeros, including the spare capacity, in a way the compiler cannot
+/// optimize away
+pub fn vec(bytes: &mut Vec<u8>) {
+  for byte in bytes.iter_mut() {
+    unsafe { ptr::write_volatile(byte, 0) };
+  }
+  for byte in bytes.spare_capacity_mut() {
+    unsafe { ptr::write_volatile(byte.as_mut_ptr(), 0) };
+  }
+  compiler_fence(Ordering::SeqCst);
+}
+
+/// Overwrites the text with zeros, see [`vec`]
+#[cfg_attr(any(windows, target_os = "macos"), allow(dead_code))]
+pub fn string(text: &mut String) {
+  // Zeros are valid UTF-8
+  vec(unsafe { text.as_mut_vec() });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn overwrites_bytes_with_zeros() {
+    let mut text = String::with_capacity(16);
+    text.push_str("secret");
+    string(&mut text);
+    assert_eq!(text.as_bytes(), [0; 6]);
+    assert_eq!(text.capacity(), 16);
+  }
+}